@@ -0,0 +1,396 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+//! Checks shared by the fuzz targets. Every check loads the input and walks
+//! all entries of the matching sections or modifies them. Results are
+//! ignored: the only requirement is that nothing panics
+
+use std::io;
+
+use elfio::*;
+
+// --------------------------------------------------------------------------
+/// Load an ELF file from the fuzzer input
+pub fn load(data: &[u8]) -> Option<Elfio<'static>> {
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(data)).ok()?;
+
+    Some(elf)
+}
+
+// --------------------------------------------------------------------------
+fn sections_of_type(elf: &Elfio, types: &[ElfWord]) -> Vec<ElfWord> {
+    elf.get_sections()
+        .iter()
+        .filter(|section| types.contains(&section.get_type()))
+        .map(|section| section.get_index())
+        .collect()
+}
+
+// --------------------------------------------------------------------------
+fn read_headers(elf: &Elfio) {
+    let _ = elf.get_entry();
+    let _ = elf.get_section_name_str_index();
+    for section in elf.get_sections() {
+        let _ = (section.get_name(), section.get_data().len());
+        let _ = elf.get_segments_for_section(section.get_index());
+    }
+    for segment in elf.get_segments() {
+        let _ = (segment.get_offset(), segment.get_data().len());
+        let _ = segment.get_section_indices();
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read ELF header, section and segment attributes and their mapping
+pub fn check_load(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        read_headers(&elf);
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read the same as `check_load` from an ELF file borrowing the input
+pub fn check_from_bytes(data: &[u8]) {
+    if let Ok(elf) = Elfio::from_bytes(data) {
+        read_headers(&elf);
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read the same as `check_load` from an ELF file reading the section
+/// and segment data on demand
+pub fn check_load_lazy(data: &[u8]) {
+    let mut elf = Elfio::new();
+    if elf.load_lazy(io::Cursor::new(data.to_vec())).is_ok() {
+        read_headers(&elf);
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read strings at every offset of string tables
+pub fn check_strings(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_STRTAB]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let strings = StringSectionAccessor::new(&elf, section);
+            for pos in 0..=section.get_data().len() as ElfWord + 1 {
+                let _ = strings.get_string(pos);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read all symbols of symbol tables
+pub fn check_symbols(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_SYMTAB, constant::SHT_DYNSYM]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let symbols = SymbolSectionAccessor::new(&elf, section);
+            for i in 0..=symbols.get_symbols_num() {
+                let _ = symbols.get_symbol(i);
+            }
+            let _ = symbols.find_by_name("main");
+            let _ = symbols.find_by_address(0x1000);
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read all entries of relocation tables
+pub fn check_relocations(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_REL, constant::SHT_RELA]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let relocations = RelocationSectionAccessor::new(&elf, section);
+            for i in 0..=relocations.get_entries_num() {
+                let _ = relocations.get_entry(i);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read all entries of dynamic sections
+pub fn check_dynamic(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_DYNAMIC]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let dynamic = DynamicSectionAccessor::new(&elf, section);
+            for i in 0..=dynamic.get_entries_num() {
+                let _ = dynamic.get_entry(i);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read all notes of note sections
+pub fn check_notes(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_NOTE]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let notes = NoteSectionAccessor::new(&elf, section);
+            for i in 0..=notes.get_notes_num() {
+                let _ = notes.get_note(i);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read all entries of init, fini and preinit arrays
+pub fn check_arrays(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        let types = [
+            constant::SHT_INIT_ARRAY,
+            constant::SHT_FINI_ARRAY,
+            constant::SHT_PREINIT_ARRAY,
+        ];
+        for index in sections_of_type(&elf, &types) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let array = ArraySectionAccessor::new(&elf, section);
+            for i in 0..=array.get_entries_num() {
+                let _ = array.get_entry(i);
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Parse every section as a modinfo table
+pub fn check_modinfo(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for section in elf.get_sections() {
+            let modinfo = ModInfoSectionAccessor::new(&elf, &**section);
+            for (key, _) in modinfo.get_iter() {
+                let _ = modinfo.get_all(key);
+            }
+            let _ = modinfo.get_depends();
+            let _ = modinfo.get_parameters();
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read SysV and GNU hash tables and look up symbols through them
+pub fn check_hash(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for index in sections_of_type(&elf, &[constant::SHT_HASH]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let hash = HashSectionAccessor::new(&elf, section);
+            for i in 0..=hash.get_buckets_num().min(1024) {
+                let _ = hash.get_bucket(i);
+            }
+            for i in 0..=hash.get_chains_num().min(1024) {
+                let _ = hash.get_chain(i);
+            }
+            let _ = hash.find_symbol("main");
+        }
+        for index in sections_of_type(&elf, &[constant::SHT_GNU_HASH]) {
+            let section = elf.get_section_by_index(index).unwrap();
+            let hash = GnuHashSectionAccessor::new(&elf, section);
+            for i in 0..=hash.get_bloom_size().min(1024) {
+                let _ = hash.get_bloom_word(i);
+            }
+            for i in 0..=hash.get_buckets_num().min(1024) {
+                let _ = hash.get_bucket(i);
+            }
+            for i in 0..=hash.get_chains_num().min(1024) {
+                let _ = hash.get_chain(i);
+            }
+            let _ = hash.find_symbol("main");
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read symbol version tables, definitions and requirements
+pub fn check_versions(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for section in elf.get_sections() {
+            let section = &**section;
+            match section.get_type() {
+                constant::SHT_GNU_VERSYM => {
+                    let versym = VersymSectionAccessor::new(&elf, section);
+                    for i in 0..=versym.get_entries_num() {
+                        let _ = versym.get_entry(i);
+                    }
+                }
+                constant::SHT_GNU_VERDEF => {
+                    let _ = VerdefSectionAccessor::new(&elf, section).get_entries();
+                }
+                constant::SHT_GNU_VERNEED => {
+                    let _ = VerneedSectionAccessor::new(&elf, section).get_entries();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Get memory images of all segments, the images are not materialized
+pub fn check_memory_image(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        for segment in elf.get_segments() {
+            let (data, zeros) = segment.get_memory_image();
+            let _ = (data.len(), zeros);
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Read the beginning and the end of every region of the address space
+pub fn check_address_space(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        let memory = AddressSpace::new(&elf);
+        for segment in elf.get_segments() {
+            let address = segment.get_virtual_address();
+            let end = address.wrapping_add(segment.get_memory_size());
+            let _ = memory.read(address, 16);
+            let _ = memory.read_u64(end.wrapping_sub(8));
+            let _ = memory.get_file_offset(address);
+            let _ = memory.get_address(segment.get_offset());
+        }
+        for section in elf.get_sections() {
+            let _ = memory.read(section.get_address(), section.get_size().min(4096));
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Place the program image into memory, the memory itself is not kept
+pub fn check_load_image(data: &[u8]) {
+    if let Some(elf) = load(data) {
+        let _ = load_image(&elf, |_, data, zeros, _| {
+            let _ = (data.len(), zeros);
+            Ok(())
+        });
+    }
+}
+
+// --------------------------------------------------------------------------
+fn modify_tables(elf: &mut Elfio) {
+    let symbol = Symbol {
+        name:  "fuzz".to_string(),
+        value: 0x1000,
+        size:  8,
+        ..Default::default()
+    };
+    for index in sections_of_type(elf, &[constant::SHT_SYMTAB, constant::SHT_DYNSYM]) {
+        if let Some(mut symbols) = SymbolSectionAccessorMut::new(elf, index) {
+            let _ = symbols.add_symbol(&symbol, |_, _| ());
+            let global = Symbol {
+                bind: constant::STB_GLOBAL,
+                ..symbol.clone()
+            };
+            let _ = symbols.add_symbol(&global, |_, _| ());
+            let _ = symbols.update_symbol(1, &global, |_, _| ());
+            symbols.arrange_local_symbols(|_, _| ());
+            let _ = symbols.remove_symbol(symbols.get_symbols_num().saturating_sub(1), |_, _| ());
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_REL, constant::SHT_RELA]) {
+        if let Some(mut relocations) = RelocationSectionAccessorMut::new(elf, index) {
+            let relocation = Relocation {
+                offset: 0x1000,
+                symbol: 1,
+                rtype:  1,
+                addend: Some(-4),
+            };
+            let _ = relocations.add_entry(&relocation);
+            let _ = relocations.set_entry(0, &relocation);
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_DYNAMIC]) {
+        if let Some(mut dynamic) = DynamicSectionAccessorMut::new(elf, index) {
+            let _ = dynamic.add_needed("libfuzz.so");
+            let _ = dynamic.set_runpath("/fuzz");
+            let _ = dynamic.remove_entry(0);
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_NOTE]) {
+        if let Some(mut notes) = NoteSectionAccessorMut::new(elf, index) {
+            let _ = notes.add_note(1, "fuzz", &[1, 2, 3]);
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_INIT_ARRAY, constant::SHT_FINI_ARRAY]) {
+        if let Some(mut array) = ArraySectionAccessorMut::new(elf, index) {
+            let _ = array.add_entry(0x1000);
+            let _ = array.add_entry_with_relocation(0x1000, 1, 1);
+            let _ = array.set_entry(0, 0x2000);
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_STRTAB]) {
+        if let Some(section) = elf.get_section_by_index_mut(index) {
+            let _ = StringSectionAccessorMut::new(section).find_or_add_string("fuzz");
+        }
+    }
+    if let Some(index) = elf.get_section_by_name(".modinfo").map(|s| s.get_index()) {
+        if let Some(mut modinfo) = ModInfoSectionAccessorMut::new(elf, index) {
+            modinfo.set("license", "GPL");
+            let _ = modinfo.remove("author");
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_HASH]) {
+        if let Some(mut hash) = HashSectionAccessorMut::new(elf, index) {
+            let _ = hash.generate();
+        }
+    }
+    for index in sections_of_type(elf, &[constant::SHT_GNU_HASH]) {
+        if let Some(mut hash) = GnuHashSectionAccessorMut::new(elf, index) {
+            let _ = hash.generate(|_, _| ());
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// Modify the tables through the mutable accessors, write the beginning
+/// of every segment and section through the address space and save
+/// the result
+pub fn check_modify(data: &[u8]) {
+    if let Some(mut elf) = load(data) {
+        modify_tables(&mut elf);
+
+        let addresses: Vec<Elf64Addr> = elf
+            .get_segments()
+            .iter()
+            .map(|segment| segment.get_virtual_address())
+            .chain(elf.get_sections().iter().map(|section| section.get_address()))
+            .collect();
+        let mut memory = AddressSpaceMut::new(&mut elf);
+        for address in addresses {
+            let _ = memory.write(address, &[0x90; 3]);
+            let _ = memory.write_u64(address.wrapping_add(5), 0x0102030405060708);
+        }
+
+        // Alignments of malformed files may place the data at huge offsets.
+        // The buffer is bounded, so such files fail to be saved instead of
+        // exhausting the memory
+        let mut buffer = vec![0; 16 << 20];
+        if elf.save(&mut io::Cursor::new(&mut buffer[..])).is_ok() {
+            let _ = Elfio::from_bytes(&buffer);
+        }
+    }
+}
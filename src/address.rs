@@ -0,0 +1,397 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+use std::convert::TryFrom;
+
+use super::elfio::*;
+use super::types::*;
+use super::utils::Convert;
+
+// A range of the address space backed by a loadable segment or,
+// for relocatable files, by an allocatable section
+#[derive(Debug)]
+struct Region {
+    address:   Elf64Addr,
+    size:      ElfXword,
+    file_size: ElfXword,
+    offset:    Elf64Off,
+    segment:   Option<ElfWord>,
+    section:   Option<ElfWord>,
+}
+
+// The storage of a part of the address space
+#[derive(Debug, Clone, Copy)]
+enum Source {
+    Section(ElfWord),
+    Segment(ElfWord),
+    Zero,
+}
+
+// A contiguous part of the requested memory range kept by a single storage
+#[derive(Debug)]
+struct Chunk {
+    source:   Source,
+    position: usize,
+    size:     usize,
+}
+
+/// A view of the ELF file contents by virtual addresses. The address space
+/// is made of PT_LOAD segments, or of SHF_ALLOC sections for relocatable
+/// files. The bytes are taken from the sections covering them or from
+/// the segment data otherwise. The memory following the segment file image,
+/// as well as SHT_NOBITS sections, is read as zeros
+///
+/// For example:
+/// ```
+/// use elfio::{AddressSpace, Elfio};
+///
+/// let data = std::fs::read("tests/files/hello_64").unwrap();
+/// let elf = Elfio::from_bytes(&data).unwrap();
+///
+/// let memory = AddressSpace::new(&elf);
+/// assert_eq!(memory.read(0x400200, 12).unwrap(), b"/lib64/ld-li");
+/// assert_eq!(memory.read_u32(0x400000), Some(0x464C457F));
+/// assert_eq!(memory.get_file_offset(0x600698), Some(0x698));
+/// assert_eq!(memory.get_address(0x698), Some(0x600698));
+///
+/// // .bss
+/// assert_eq!(memory.read_u64(0x600860), Some(0));
+/// assert_eq!(memory.get_file_offset(0x600860), None);
+/// assert_eq!(memory.read(0x600870, 1), None);
+/// ```
+pub struct AddressSpace<'a> {
+    elfio:   &'a Elfio<'a>,
+    regions: Vec<Region>,
+}
+
+impl<'a> AddressSpace<'a> {
+    /// Creates a new instance of the address space view
+    pub fn new(elfio: &'a Elfio<'a>) -> AddressSpace<'a> {
+        AddressSpace {
+            elfio,
+            regions: get_regions(elfio),
+        }
+    }
+
+    /// Reads `size` bytes starting at the address. Returns `None` if any
+    /// part of the range doesn't belong to the address space
+    pub fn read(&self, address: Elf64Addr, size: ElfXword) -> Option<Vec<u8>> {
+        read(self.elfio, &self.regions, address, size)
+    }
+
+    /// Reads a 16-bit value stored at the address in the file byte order
+    pub fn read_u16(&self, address: Elf64Addr) -> Option<u16> {
+        let data = self.read(address, 2)?;
+        let converter = self.elfio.get_converter();
+        Some(converter.convert(u16::from_ne_bytes(<[u8; 2]>::try_from(&data[..]).ok()?)))
+    }
+
+    /// Reads a 32-bit value stored at the address in the file byte order
+    pub fn read_u32(&self, address: Elf64Addr) -> Option<u32> {
+        let data = self.read(address, 4)?;
+        let converter = self.elfio.get_converter();
+        Some(converter.convert(u32::from_ne_bytes(<[u8; 4]>::try_from(&data[..]).ok()?)))
+    }
+
+    /// Reads a 64-bit value stored at the address in the file byte order
+    pub fn read_u64(&self, address: Elf64Addr) -> Option<u64> {
+        let data = self.read(address, 8)?;
+        let converter = self.elfio.get_converter();
+        Some(converter.convert(u64::from_ne_bytes(<[u8; 8]>::try_from(&data[..]).ok()?)))
+    }
+
+    /// Returns the file offset of the byte at the address. Returns `None`
+    /// if the address doesn't belong to the file image of the address space
+    pub fn get_file_offset(&self, address: Elf64Addr) -> Option<Elf64Off> {
+        let region = find_region(&self.regions, address)?;
+        let position = address - region.address;
+        if position < region.file_size {
+            Some(region.offset + position)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the address the byte at the file offset is loaded to.
+    /// Returns `None` if the byte doesn't belong to the address space
+    pub fn get_address(&self, offset: Elf64Off) -> Option<Elf64Addr> {
+        self.regions
+            .iter()
+            .find(|region| offset >= region.offset && offset - region.offset < region.file_size)
+            .map(|region| region.address + (offset - region.offset))
+    }
+}
+
+/// A view of the ELF file contents by virtual addresses intended to modify
+/// the contents. The bytes are written to the sections covering them or to
+/// the segment data otherwise. The memory which has no file image, like
+/// `.bss`, can't be written
+///
+/// For example:
+/// ```
+/// use elfio::{AddressSpaceMut, Elfio};
+///
+/// let data = std::fs::read("tests/files/hello_64").unwrap();
+/// let mut elf = Elfio::from_bytes(&data).unwrap();
+///
+/// let mut memory = AddressSpaceMut::new(&mut elf);
+/// assert!(memory.write(0x400200, b"/lib64/ld-elfio"));
+/// assert!(memory.write_u32(0x600850, 0x12345678));
+/// assert!(!memory.write_u32(0x600860, 1));
+/// assert_eq!(memory.read(0x400200, 15).unwrap(), b"/lib64/ld-elfio");
+///
+/// let section = elf.get_section_by_name(".interp").unwrap();
+/// assert_eq!(&section.get_data()[..16], b"/lib64/ld-elfio-");
+/// let section = elf.get_section_by_name(".got.plt").unwrap();
+/// assert_eq!(section.get_data()[32..36], [0x78, 0x56, 0x34, 0x12]);
+/// ```
+pub struct AddressSpaceMut<'a, 'b> {
+    elfio:   &'a mut Elfio<'b>,
+    regions: Vec<Region>,
+}
+
+impl<'a, 'b> AddressSpaceMut<'a, 'b> {
+    /// Creates a new instance of the address space view
+    pub fn new(elfio: &'a mut Elfio<'b>) -> AddressSpaceMut<'a, 'b> {
+        let regions = get_regions(elfio);
+        AddressSpaceMut { elfio, regions }
+    }
+
+    /// Reads `size` bytes starting at the address. Returns `None` if any
+    /// part of the range doesn't belong to the address space
+    pub fn read(&self, address: Elf64Addr, size: ElfXword) -> Option<Vec<u8>> {
+        read(self.elfio, &self.regions, address, size)
+    }
+
+    /// Writes the bytes starting at the address. Nothing is written and
+    /// `false` is returned if any part of the range doesn't belong to
+    /// the file image of the address space or has no data kept for it
+    pub fn write(&mut self, address: Elf64Addr, bytes: &[u8]) -> bool {
+        let chunks = match get_chunks(self.elfio, &self.regions, address, bytes.len() as ElfXword) {
+            Some(chunks) => chunks,
+            None => return false,
+        };
+        // The data kept may be shorter than the section or segment size
+        let is_writable = chunks.iter().all(|chunk| {
+            let data_size = match chunk.source {
+                Source::Section(index) => self
+                    .elfio
+                    .get_section_by_index(index)
+                    .and_then(|section| section.try_get_data().ok())
+                    .map(|data| data.len()),
+                Source::Segment(index) => self
+                    .elfio
+                    .get_segment_by_index(index)
+                    .and_then(|segment| segment.try_get_data().ok())
+                    .map(|data| data.len()),
+                Source::Zero => None,
+            };
+            data_size.is_some_and(|size| chunk.position + chunk.size <= size)
+        });
+        if !is_writable {
+            return false;
+        }
+
+        let mut pos = 0;
+        for chunk in chunks {
+            let part = &bytes[pos..pos + chunk.size];
+            let range = chunk.position..chunk.position + chunk.size;
+            let data = match chunk.source {
+                Source::Section(index) => self
+                    .elfio
+                    .get_section_by_index_mut(index)
+                    .and_then(|section| section.get_data_mut().ok()),
+                Source::Segment(index) => self
+                    .elfio
+                    .get_segment_by_index_mut(index)
+                    .and_then(|segment| segment.get_data_mut().ok()),
+                Source::Zero => None,
+            };
+            if let Some(target) = data.and_then(|data| data.get_mut(range)) {
+                target.copy_from_slice(part);
+            }
+            pos += chunk.size;
+        }
+
+        true
+    }
+
+    /// Writes a 16-bit value at the address in the file byte order
+    pub fn write_u16(&mut self, address: Elf64Addr, value: u16) -> bool {
+        let value = self.elfio.get_converter().convert(value);
+        self.write(address, &value.to_ne_bytes())
+    }
+
+    /// Writes a 32-bit value at the address in the file byte order
+    pub fn write_u32(&mut self, address: Elf64Addr, value: u32) -> bool {
+        let value = self.elfio.get_converter().convert(value);
+        self.write(address, &value.to_ne_bytes())
+    }
+
+    /// Writes a 64-bit value at the address in the file byte order
+    pub fn write_u64(&mut self, address: Elf64Addr, value: u64) -> bool {
+        let value = self.elfio.get_converter().convert(value);
+        self.write(address, &value.to_ne_bytes())
+    }
+}
+
+// Collects the loadable segments, or the allocatable sections of
+// a relocatable file, sorted by their addresses
+fn get_regions(elfio: &Elfio) -> Vec<Region> {
+    let mut regions: Vec<Region> = if elfio.get_type() == constant::ET_REL {
+        elfio
+            .get_sections()
+            .iter()
+            .filter(|section| section.get_flags() & constant::SHF_ALLOC != 0)
+            .map(|section| Region {
+                address:   section.get_address(),
+                size:      section.get_size(),
+                file_size: if section.get_type() == constant::SHT_NOBITS {
+                    0
+                } else {
+                    section.get_size()
+                },
+                offset:    section.get_offset(),
+                segment:   None,
+                section:   Some(section.get_index()),
+            })
+            .collect()
+    } else {
+        elfio
+            .get_segments()
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| segment.get_type() == constant::PT_LOAD)
+            .map(|(index, segment)| Region {
+                address:   segment.get_virtual_address(),
+                size:      std::cmp::max(segment.get_memory_size(), segment.get_file_size()),
+                file_size: segment.get_file_size(),
+                offset:    segment.get_offset(),
+                segment:   Some(index as ElfWord),
+                section:   None,
+            })
+            .collect()
+    };
+
+    regions.retain(|region| region.size != 0);
+    regions.sort_by_key(|region| region.address);
+    regions
+}
+
+// Returns the region containing the address
+fn find_region(regions: &[Region], address: Elf64Addr) -> Option<&Region> {
+    let pos = regions.partition_point(|region| region.address <= address);
+    regions[..pos]
+        .iter()
+        .rev()
+        .find(|region| address - region.address < region.size)
+}
+
+// Splits the memory range to the parts kept by different storages
+fn get_chunks(
+    elfio: &Elfio,
+    regions: &[Region],
+    address: Elf64Addr,
+    size: ElfXword,
+) -> Option<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    let mut address = address;
+    let mut size = size;
+    while size != 0 {
+        let region = find_region(regions, address)?;
+        let position = address - region.address;
+        let (source, position, available) = if position >= region.file_size {
+            (Source::Zero, 0, region.size - position)
+        } else if let Some(index) = region.section {
+            (Source::Section(index), position, region.file_size - position)
+        } else {
+            get_segment_source(elfio, region, position)?
+        };
+
+        let chunk_size = std::cmp::min(available, size);
+        chunks.push(Chunk {
+            source,
+            position: usize::try_from(position).ok()?,
+            size: usize::try_from(chunk_size).ok()?,
+        });
+        address = address.checked_add(chunk_size)?;
+        size -= chunk_size;
+    }
+
+    Some(chunks)
+}
+
+// Finds the storage of a segment byte. It is the section containing
+// the byte, if any, or the segment data itself
+fn get_segment_source(
+    elfio: &Elfio,
+    region: &Region,
+    position: ElfXword,
+) -> Option<(Source, ElfXword, ElfXword)> {
+    let index = region.segment?;
+    let segment = elfio.get_segment_by_index(index)?;
+    let offset = region.offset + position;
+    let segment_end = region.offset + region.file_size;
+
+    let mut next_start = segment_end;
+    for section_index in segment.get_section_indices() {
+        let section = match elfio.get_section_by_index(*section_index) {
+            Some(section) => section,
+            None => continue,
+        };
+        if section.get_type() == constant::SHT_NOBITS || section.get_size() == 0 {
+            continue;
+        }
+
+        let start = section.get_offset();
+        let end = start.saturating_add(section.get_size());
+        if start <= offset && offset < end {
+            let available = std::cmp::min(end, segment_end) - offset;
+            return Some((Source::Section(*section_index), offset - start, available));
+        }
+        if start > offset {
+            next_start = std::cmp::min(next_start, start);
+        }
+    }
+
+    Some((Source::Segment(index), position, next_start - offset))
+}
+
+fn read(elfio: &Elfio, regions: &[Region], address: Elf64Addr, size: ElfXword) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    for chunk in get_chunks(elfio, regions, address, size)? {
+        let range = chunk.position..chunk.position + chunk.size;
+        match chunk.source {
+            Source::Section(index) => {
+                let section = elfio.get_section_by_index(index)?;
+                data.extend_from_slice(section.try_get_data().ok()?.get(range)?);
+            }
+            Source::Segment(index) => {
+                let segment = elfio.get_segment_by_index(index)?;
+                data.extend_from_slice(segment.try_get_data().ok()?.get(range)?);
+            }
+            Source::Zero => data.resize(data.len() + chunk.size, 0),
+        }
+    }
+
+    Some(data)
+}
@@ -23,6 +23,7 @@ THE SOFTWARE.
 use std::convert::TryFrom;
 
 use super::elfio::*;
+use super::relocation::*;
 use super::section::*;
 use super::types::*;
 use super::utils::Convert;
@@ -85,30 +86,28 @@ struct Elf64Array {
 /// }
 /// ```
 pub struct ArraySectionAccessor<'a> {
-    elfio:   &'a Elfio,
+    elfio:   &'a Elfio<'a>,
     section: &'a dyn ElfSectionTrait,
 }
 
 impl<'a> ArraySectionAccessor<'a> {
     /// Creates a new instance of the relocation table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> ArraySectionAccessor<'a> {
+    pub fn new(elfio: &'a Elfio<'a>, section: &'a dyn ElfSectionTrait) -> ArraySectionAccessor<'a> {
         ArraySectionAccessor { elfio, section }
     }
 
     /// Returns number of symbols
     pub fn get_entries_num(&self) -> ElfXword {
-        let entry_size;
+        self.section.get_data_size() / self.get_entry_size()
+    }
+
+    // Returns the size of a single array element
+    fn get_entry_size(&self) -> ElfXword {
         if self.elfio.get_class() == constant::ELFCLASS64 {
-            entry_size = 8;
+            8
         } else {
-            entry_size = 4;
-        }
-
-        if entry_size != 0 {
-            return self.section.get_size() / entry_size;
+            4
         }
-
-        0
     }
 
     /// Get a relocation entry by its index
@@ -118,16 +117,10 @@ impl<'a> ArraySectionAccessor<'a> {
             return None;
         }
 
-        let entry_size;
-        if self.elfio.get_class() == constant::ELFCLASS64 {
-            entry_size = 8;
-        } else {
-            entry_size = 4;
-        }
-
+        let entry_size = self.get_entry_size();
         let offset: usize = (index * entry_size) as usize;
         let end: usize = offset + entry_size as usize;
-        let entry_area = &self.section.get_data()[offset..end];
+        let entry_area = self.section.try_get_data().ok()?.get(offset..end)?;
 
         let converter = self.elfio.get_converter();
 
@@ -153,3 +146,151 @@ impl<'a> ArraySectionAccessor<'a> {
         }
     }
 }
+
+/// A section data accessor intended to modify array tables
+///
+/// For example:
+/// ```
+/// use elfio::{constant, ArraySectionAccessorMut, Elfio};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2MSB);
+/// let init_array = elf.add_section(".init_array");
+/// init_array.set_type(constant::SHT_INIT_ARRAY);
+/// let init_array_index = init_array.get_index();
+///
+/// let mut array = ArraySectionAccessorMut::new(&mut elf, init_array_index).unwrap();
+/// assert_eq!(array.add_entry(0x10000), 0);
+/// assert_eq!(array.add_entry(0x10010), 1);
+/// assert!(array.set_entry(0, 0x10020));
+/// assert_eq!(array.get_entry(0).unwrap().value, 0x10020);
+///
+/// let section = elf.get_section_by_index(init_array_index).unwrap();
+/// assert_eq!(section.get_data(), [0, 1, 0, 0x20, 0, 1, 0, 0x10]);
+/// ```
+pub struct ArraySectionAccessorMut<'a, 'b> {
+    elfio: &'a mut Elfio<'b>,
+    index: ElfWord,
+}
+
+impl<'a, 'b> ArraySectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the array accessor for the section with
+    /// the given index. Returns `None` if there is no such section or
+    /// the data of the section or of the section it is linked to fails
+    /// to be read from a lazily loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<ArraySectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+
+        Some(ArraySectionAccessorMut { elfio, index })
+    }
+
+    /// Returns number of entries
+    pub fn get_entries_num(&self) -> ElfXword {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        ArraySectionAccessor::new(self.elfio, section).get_entries_num()
+    }
+
+    /// Get an array entry by its index
+    pub fn get_entry(&self, index: ElfXword) -> Option<Array> {
+        let section = self.elfio.get_section_by_index(self.index)?;
+        ArraySectionAccessor::new(self.elfio, section).get_entry(index)
+    }
+
+    /// Appends an entry to the array and returns its index
+    pub fn add_entry(&mut self, value: Elf64Addr) -> ElfXword {
+        let index = self.get_entries_num();
+        let entry = self.encode(value);
+        let section = self.get_section_mut();
+        let mut data = section.get_data().to_vec();
+        data.truncate(index as usize * entry.len());
+        data.extend_from_slice(&entry);
+        section.set_data(&data);
+
+        index
+    }
+
+    /// Appends an entry to the array of a relocatable object together with
+    /// the relocation for it. The relocation is added to the relocation
+    /// section applied to the array section, `.rela.init_array` for
+    /// `.init_array`, for example. For SHT_RELA relocation sections
+    /// the value is stored as the relocation addend and the array entry
+    /// is zero. Returns `None` if there is no such relocation section
+    pub fn add_entry_with_relocation(
+        &mut self,
+        value: Elf64Addr,
+        symbol: ElfWord,
+        rtype: ElfWord,
+    ) -> Option<ElfXword> {
+        let rel_index = self
+            .elfio
+            .get_sections()
+            .iter()
+            .find(|section| {
+                (section.get_type() == constant::SHT_REL
+                    || section.get_type() == constant::SHT_RELA)
+                    && section.get_info() == self.index
+            })?
+            .get_index();
+        let is_rela =
+            self.elfio.get_section_by_index(rel_index)?.get_type() == constant::SHT_RELA;
+
+        let index = self.add_entry(if is_rela { 0 } else { value });
+        let relocation = Relocation {
+            offset: index * self.get_entry_size(),
+            symbol,
+            rtype,
+            addend: if is_rela {
+                Some(value as ElfSxword)
+            } else {
+                None
+            },
+        };
+        RelocationSectionAccessorMut::new(self.elfio, rel_index)?.add_entry(&relocation);
+
+        Some(index)
+    }
+
+    /// Replaces the entry with the given index. Returns `false` if there
+    /// is no such entry
+    pub fn set_entry(&mut self, index: ElfXword, value: Elf64Addr) -> bool {
+        if index >= self.get_entries_num() {
+            return false;
+        }
+
+        let entry = self.encode(value);
+        let section = self.get_section_mut();
+        let mut data = section.get_data().to_vec();
+        let offset = index as usize * entry.len();
+        data[offset..offset + entry.len()].copy_from_slice(&entry);
+        section.set_data(&data);
+
+        true
+    }
+
+    // Returns the size of a single array element
+    fn get_entry_size(&self) -> ElfXword {
+        if self.elfio.get_class() == constant::ELFCLASS64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    // Converts the value to the array entry
+    fn encode(&self, value: Elf64Addr) -> Vec<u8> {
+        let converter = self.elfio.get_converter();
+        if self.elfio.get_class() == constant::ELFCLASS64 {
+            converter.convert(value).to_ne_bytes().to_vec()
+        } else {
+            converter.convert(value as u32).to_ne_bytes().to_vec()
+        }
+    }
+
+    // The section existence is checked on the accessor creation
+    fn get_section_mut(&mut self) -> &mut dyn ElfSectionTrait {
+        self.elfio.get_section_by_index_mut(self.index).unwrap()
+    }
+}
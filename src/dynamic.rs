@@ -23,14 +23,16 @@ THE SOFTWARE.
 use std::convert::TryFrom;
 
 use super::elfio::*;
+use super::error::*;
 use super::section::*;
+use super::strings::*;
 use super::types::*;
 use super::utils::Convert;
 
 /// A dynamic table element
 ///
 /// See documentation for [DynamicSectionAccessor] for usage example
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Dynamic {
     /// Identifies the type of dynamic table entry
     pub tag:   ElfSxword,
@@ -93,13 +95,16 @@ struct Elf64Dyn {
 /// }
 /// ```
 pub struct DynamicSectionAccessor<'a> {
-    elfio:   &'a Elfio,
+    elfio:   &'a Elfio<'a>,
     section: &'a dyn ElfSectionTrait,
 }
 
 impl<'a> DynamicSectionAccessor<'a> {
     /// Creates a new instance of the relocation table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> DynamicSectionAccessor<'a> {
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> DynamicSectionAccessor<'a> {
         DynamicSectionAccessor { elfio, section }
     }
 
@@ -108,9 +113,10 @@ impl<'a> DynamicSectionAccessor<'a> {
         let max_entries = self.get_entries_num_internal();
 
         for i in 0..max_entries {
-            let entry = self.get_entry(i).unwrap();
-            if entry.tag == constant::DT_NULL {
-                return i + 1;
+            match self.get_entry(i) {
+                Some(entry) if entry.tag == constant::DT_NULL => return i + 1,
+                Some(_) => (),
+                None => break,
             }
         }
 
@@ -120,7 +126,7 @@ impl<'a> DynamicSectionAccessor<'a> {
     // Returns number of symbols according to the section size
     fn get_entries_num_internal(&self) -> ElfXword {
         if self.section.get_entry_size() != 0 {
-            return self.section.get_size() / self.section.get_entry_size();
+            return self.section.get_data_size() / self.section.get_entry_size();
         }
 
         0
@@ -135,11 +141,15 @@ impl<'a> DynamicSectionAccessor<'a> {
 
         let offset: usize = (index * self.section.get_entry_size()) as usize;
         let end: usize = offset + self.section.get_entry_size() as usize;
-        let entry_area = &self.section.get_data()[offset..end];
+        let entry_area = self.section.try_get_data().ok()?.get(offset..end)?;
 
         let converter = self.elfio.get_converter();
 
         if self.elfio.get_class() == constant::ELFCLASS64 {
+            if entry_area.len() < std::mem::size_of::<Elf64Dyn>() {
+                return None;
+            }
+
             let entry = Elf64Dyn {
                 d_tag:   converter.convert(i64::from_ne_bytes(
                     <[u8; 8]>::try_from(&entry_area[0..8])
@@ -156,6 +166,10 @@ impl<'a> DynamicSectionAccessor<'a> {
                 value: entry.d_value,
             })
         } else {
+            if entry_area.len() < std::mem::size_of::<Elf32Dyn>() {
+                return None;
+            }
+
             let entry = Elf32Dyn {
                 d_tag:   converter.convert(i32::from_ne_bytes(
                     <[u8; 4]>::try_from(&entry_area[0..4]).unwrap_or([0u8, 0u8, 0u8, 0u8]),
@@ -172,3 +186,330 @@ impl<'a> DynamicSectionAccessor<'a> {
         }
     }
 }
+
+/// A section data accessor intended to modify dynamic tables
+///
+/// The table is kept terminated by DT_NULL. New entries take the place of
+/// the spare DT_NULL entries following the terminating one, so the section
+/// grows only when no spare entries are left. String values are stored
+/// in the string table referred by the section link, DT_STRSZ is updated
+/// accordingly.
+///
+/// The tables placed in segments, the ones of loaded executables, for
+/// example, can't grow in place. When one of them has to grow, both tables
+/// are moved to a new loadable segment following the other ones in memory
+/// and placed at the end of file on save. The string table gets
+/// the segment address and DT_STRTAB is updated with it. PT_DYNAMIC
+/// segment follows the dynamic table.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, DynamicSectionAccessorMut, Elfio, StringSectionAccessor};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let dynstr = elf.add_section(".dynstr");
+/// dynstr.set_type(constant::SHT_STRTAB);
+/// let dynstr_index = dynstr.get_index();
+/// let dynamic = elf.add_section(".dynamic");
+/// dynamic.set_type(constant::SHT_DYNAMIC);
+/// dynamic.set_link(dynstr_index as u32);
+/// let dynamic_index = dynamic.get_index();
+///
+/// let mut dyns = DynamicSectionAccessorMut::new(&mut elf, dynamic_index).unwrap();
+/// assert_eq!(dyns.add_needed("libc.so.6").unwrap(), 0);
+/// dyns.set_soname("libelfio.so.1").unwrap();
+/// assert_eq!(dyns.get_entries_num(), 3);
+///
+/// let entry = dyns.get_entry(1).unwrap();
+/// assert_eq!(entry.tag, constant::DT_SONAME);
+/// let dynstr = elf.get_section_by_index(dynstr_index).unwrap();
+/// let strings = StringSectionAccessor::new(&elf, dynstr);
+/// assert_eq!(strings.get_string(entry.value as u32), "libelfio.so.1");
+/// ```
+pub struct DynamicSectionAccessorMut<'a, 'b> {
+    elfio:   &'a mut Elfio<'b>,
+    index:   ElfWord,
+    strings: StringIndex,
+}
+
+impl<'a, 'b> DynamicSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the dynamic table accessor for the section
+    /// with the given index. Returns `None` if there is no such section or
+    /// the section entry size is set and differs from the size of dynamic
+    /// table entries of the file class, or the data of the section
+    /// or of the section it is linked to fails to be read from a lazily
+    /// loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<DynamicSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+        let entry_size = elfio.get_section_by_index(index)?.get_entry_size();
+        // Entries of other sizes can't be written without truncating them
+        let expected = if elfio.get_class() == constant::ELFCLASS64 {
+            std::mem::size_of::<Elf64Dyn>()
+        } else {
+            std::mem::size_of::<Elf32Dyn>()
+        };
+        if entry_size != 0 && entry_size != expected as ElfXword {
+            return None;
+        }
+
+        Some(DynamicSectionAccessorMut {
+            elfio,
+            index,
+            strings: StringIndex::default(),
+        })
+    }
+
+    /// Returns number of entries including the terminating DT_NULL
+    pub fn get_entries_num(&self) -> ElfXword {
+        self.with_accessor(|dyns| dyns.get_entries_num())
+    }
+
+    /// Get a dynamic table entry by its index
+    pub fn get_entry(&self, index: ElfXword) -> Option<Dynamic> {
+        self.with_accessor(|dyns| dyns.get_entry(index))
+    }
+
+    /// Replaces the entry with the given index. Returns `false` if there
+    /// is no such entry
+    pub fn set_entry(&mut self, index: ElfXword, entry: &Dynamic) -> bool {
+        if index >= self.get_capacity() {
+            return false;
+        }
+
+        self.write_entry(index, entry)
+    }
+
+    /// Adds an entry in front of the terminating DT_NULL and returns
+    /// the entry index. Returns an error if the table placed in a segment
+    /// has no spare entries and can't be moved to grow
+    pub fn add_entry(&mut self, entry: &Dynamic) -> Result<ElfXword, ElfioError> {
+        let capacity = self.get_capacity();
+        let index = match self.get_entries_num() {
+            0 => capacity,
+            num => num - 1,
+        };
+        if index + 1 >= capacity && self.is_placed_in_segment(self.index) {
+            self.move_to_new_segment()?;
+        }
+
+        self.write_entry(index, entry);
+        self.write_entry(index + 1, &Dynamic::default());
+
+        Ok(index)
+    }
+
+    /// Removes the entry with the given index. The following entries are
+    /// moved one position down and a spare DT_NULL entry is added to
+    /// the end of the table. Returns `false` if there is no such entry or
+    /// the entry is the terminating DT_NULL
+    pub fn remove_entry(&mut self, index: ElfXword) -> bool {
+        let num = self.get_entries_num();
+        if num == 0 || index >= num - 1 {
+            return false;
+        }
+
+        let capacity = self.get_capacity() as usize;
+        let section = self.get_section_mut();
+        let entry_size = section.get_entry_size() as usize;
+        let mut data = section.get_data().to_vec();
+        let offset = index as usize * entry_size;
+        let end = capacity * entry_size;
+        data.copy_within(offset + entry_size..end, offset);
+        // DT_NULL entries consist of zeros
+        data[end - entry_size..end].fill(0);
+        section.set_data(&data);
+
+        true
+    }
+
+    /// Adds a DT_NEEDED entry for the library and returns the entry index.
+    /// Returns an error if the library name or the entry can't be added
+    pub fn add_needed(&mut self, library: &str) -> Result<ElfXword, ElfioError> {
+        let value = self.add_string(library)?;
+        self.add_entry(&Dynamic {
+            tag: constant::DT_NEEDED,
+            value,
+        })
+    }
+
+    /// Sets the DT_SONAME value. Returns an error if the name or the entry
+    /// can't be added
+    pub fn set_soname(&mut self, soname: &str) -> Result<(), ElfioError> {
+        self.set_string_value(constant::DT_SONAME, soname)
+    }
+
+    /// Sets the DT_RUNPATH value. Returns an error if the path or the entry
+    /// can't be added
+    pub fn set_runpath(&mut self, runpath: &str) -> Result<(), ElfioError> {
+        self.set_string_value(constant::DT_RUNPATH, runpath)
+    }
+
+    /// Sets the DT_RPATH value. Returns an error if the path or the entry
+    /// can't be added
+    pub fn set_rpath(&mut self, rpath: &str) -> Result<(), ElfioError> {
+        self.set_string_value(constant::DT_RPATH, rpath)
+    }
+
+    /// Sets the DT_FLAGS value. Returns an error if the entry can't be added
+    pub fn set_flags(&mut self, flags: ElfXword) -> Result<(), ElfioError> {
+        self.set_value(constant::DT_FLAGS, flags)
+    }
+
+    /// Sets the DT_FLAGS_1 value. Returns an error if the entry can't be added
+    pub fn set_flags_1(&mut self, flags: ElfXword) -> Result<(), ElfioError> {
+        self.set_value(constant::DT_FLAGS_1, flags)
+    }
+
+    fn set_string_value(&mut self, tag: ElfSxword, string: &str) -> Result<(), ElfioError> {
+        let value = self.add_string(string)?;
+        self.set_value(tag, value)
+    }
+
+    // Updates the value of the first entry with the tag. The entry is
+    // added if the table doesn't have it
+    fn set_value(&mut self, tag: ElfSxword, value: ElfXword) -> Result<(), ElfioError> {
+        let num = self.get_entries_num();
+        match (0..num).find(|i| self.get_entry(*i).is_some_and(|e| e.tag == tag)) {
+            Some(index) => {
+                self.write_entry(index, &Dynamic { tag, value });
+            }
+            None => {
+                self.add_entry(&Dynamic { tag, value })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Adds the string to the linked string table and returns its index.
+    // DT_STRSZ is updated with the new string table size. Returns an error
+    // if the section link doesn't refer to a string table
+    fn add_string(&mut self, string: &str) -> Result<ElfXword, ElfioError> {
+        let link = self.get_section_mut().get_link();
+        match self.elfio.get_section_by_index(link) {
+            Some(strtab) if link != self.index && strtab.get_type() == constant::SHT_STRTAB => {
+                if let Some(index) = self.strings.find_string(strtab.get_data(), string) {
+                    return Ok(index as ElfXword);
+                }
+            }
+            _ => return Err(ElfioError::MissingStringTable { section: self.index }),
+        }
+        if self.is_placed_in_segment(link) {
+            self.move_to_new_segment()?;
+        }
+
+        // The string table existence is checked above
+        let strtab = self.elfio.get_section_by_index_mut(link).unwrap();
+        let index = self.strings.find_or_add_string(strtab, string);
+        let size = strtab.get_size();
+
+        let num = self.get_entries_num();
+        let strsz = (0..num).find(|i| {
+            self.get_entry(*i)
+                .is_some_and(|e| e.tag == constant::DT_STRSZ)
+        });
+        if let Some(strsz) = strsz {
+            self.write_entry(
+                strsz,
+                &Dynamic {
+                    tag:   constant::DT_STRSZ,
+                    value: size,
+                },
+            );
+        }
+
+        Ok(index as ElfXword)
+    }
+
+    // Moves the string table and the dynamic table placed in segments to
+    // a new loadable segment, where they can grow. DT_STRTAB is updated
+    // with the new string table address
+    fn move_to_new_segment(&mut self) -> Result<(), ElfioError> {
+        let link = self.get_section_mut().get_link();
+        let indices: Vec<ElfWord> = [link, self.index]
+            .iter()
+            .copied()
+            .filter(|index| self.is_placed_in_segment(*index))
+            .collect();
+        if indices.is_empty() {
+            return Ok(());
+        }
+
+        let address = self.elfio.move_sections_to_new_segment(&indices)?;
+        if indices[0] == link {
+            self.set_value(constant::DT_STRTAB, address)?;
+        }
+
+        Ok(())
+    }
+
+    // Checks whether the section is placed in a segment, so it can't grow
+    // in place
+    fn is_placed_in_segment(&self, index: ElfWord) -> bool {
+        self.elfio
+            .get_section_by_index(index)
+            .is_some_and(|section| section.is_offset_set())
+            && !self.elfio.get_segments_for_section(index).is_empty()
+    }
+
+    // Returns number of entries according to the section size
+    fn get_capacity(&self) -> ElfXword {
+        self.with_accessor(|dyns| dyns.get_entries_num_internal())
+    }
+
+    // Writes the entry at the given index. The entry is appended if
+    // the index is equal to the number of entries in the section
+    fn write_entry(&mut self, index: ElfXword, entry: &Dynamic) -> bool {
+        let converter = *self.elfio.get_converter();
+        let mut data = Vec::new();
+        if self.elfio.get_class() == constant::ELFCLASS64 {
+            data.extend_from_slice(&converter.convert(entry.tag).to_ne_bytes());
+            data.extend_from_slice(&converter.convert(entry.value).to_ne_bytes());
+        } else {
+            data.extend_from_slice(&converter.convert(entry.tag as i32).to_ne_bytes());
+            data.extend_from_slice(&converter.convert(entry.value as u32).to_ne_bytes());
+        }
+
+        let capacity = self.get_capacity();
+        let section = self.get_section_mut();
+        if section.get_entry_size() == 0 {
+            section.set_entry_size(data.len() as ElfXword);
+        }
+        let entry_size = section.get_entry_size() as usize;
+
+        if index == capacity {
+            let mut section_data = section.get_data().to_vec();
+            section_data.truncate(capacity as usize * entry_size);
+            section_data.extend_from_slice(&data);
+            section.set_data(&section_data);
+            return true;
+        }
+
+        let mut section_data = section.get_data().to_vec();
+        let offset = index as usize * entry_size;
+        match section_data.get_mut(offset..offset + data.len()) {
+            Some(area) => area.copy_from_slice(&data),
+            None => return false,
+        }
+        section.set_data(&section_data);
+
+        true
+    }
+
+    fn with_accessor<T, F>(&self, func: F) -> T
+    where
+        F: FnOnce(&DynamicSectionAccessor) -> T,
+    {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        func(&DynamicSectionAccessor::new(self.elfio, section))
+    }
+
+    // The section existence is checked on the accessor creation
+    fn get_section_mut(&mut self) -> &mut dyn ElfSectionTrait {
+        self.elfio.get_section_by_index_mut(self.index).unwrap()
+    }
+}
@@ -36,8 +36,12 @@ THE SOFTWARE.
    limitations under the License.
 */
 
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::io;
+use std::rc::Rc;
 
+use super::error::*;
 use super::header::*;
 use super::section::*;
 use super::segment::*;
@@ -45,48 +49,53 @@ use super::strings::*;
 use super::types::*;
 use super::utils::*;
 
+// --------------------------------------------------------------------------
 /// Elfio - the main struct of the library. All access to ELF files attributes
 /// starts from this object.
 /// The object provides functions to access ELF file header attributes as well
 /// as the list of segments and sections of this file.
-
-// --------------------------------------------------------------------------
-pub struct Elfio {
+pub struct Elfio<'a> {
     header:    Box<dyn ElfHeaderTrait>,
     converter: Converter,
-    sections:  Vec<Box<dyn ElfSectionTrait>>,
-    segments:  Vec<Box<dyn ElfSegmentTrait>>,
+    sections:  Vec<Box<dyn ElfSectionTrait + 'a>>,
+    segments:  Vec<Box<dyn ElfSegmentTrait + 'a>>,
 }
 
 // --------------------------------------------------------------------------
-impl Elfio {
+impl<'a> Elfio<'a> {
     /// Create a new instance
     pub fn new() -> Self {
+        let converter = Converter { is_needed: false };
         Elfio {
-            converter: Converter { is_needed: false },
-            header:    Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new()),
-            sections:  Vec::new(),
-            segments:  Vec::new(),
+            converter,
+            header: Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new(
+                &converter,
+                constant::ELFDATANONE,
+            )),
+            sections: Vec::new(),
+            segments: Vec::new(),
         }
     }
 
     /// Create a new instance with defined encoding and endianess
     pub fn new_(encoding: u8, endianess: u8) -> Self {
+        let converter = if (endianess == constant::ELFDATA2LSB && cfg!(target_endian = "little"))
+            || endianess == constant::ELFDATA2MSB && cfg!(target_endian = "big")
+        {
+            Converter { is_needed: false }
+        } else {
+            Converter { is_needed: true }
+        };
+
         Elfio {
-            converter: if (endianess == constant::ELFDATA2LSB && cfg!(target_endian = "little"))
-                || endianess == constant::ELFDATA2MSB && cfg!(target_endian = "big")
-            {
-                Converter { is_needed: false }
+            converter,
+            header: if encoding == constant::ELFCLASS64 {
+                Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new(&converter, endianess))
             } else {
-                Converter { is_needed: true }
+                Box::new(ElfHeader::<Elf32Addr, Elf32Off>::new(&converter, endianess))
             },
-            header:    if encoding == constant::ELFCLASS64 {
-                Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new())
-            } else {
-                Box::new(ElfHeader::<Elf32Addr, Elf32Off>::new())
-            },
-            sections:  Vec::new(),
-            segments:  Vec::new(),
+            sections: Vec::new(),
+            segments: Vec::new(),
         }
     }
 
@@ -95,11 +104,74 @@ impl Elfio {
         &self.converter
     }
 
-    /// Load the ELF file from input stream
-    pub fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    /// Load the ELF file from input stream. The file content is read once,
+    /// the sections and the segments refer to the parts of it they cover
+    pub fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> Result<(), ElfioError> {
+        self.load_headers(reader, None)?;
+        let mut content = Vec::new();
+        reader.seek(io::SeekFrom::Start(0))?;
+        reader.read_to_end(&mut content)?;
+        let content = Rc::new(content);
+        for section in self.sections.iter_mut() {
+            section.set_shared_data(&content)?;
+        }
+        for segment in self.segments.iter_mut() {
+            segment.set_shared_data(&content)?;
+        }
+        self.load_section_names();
+
+        Ok(())
+    }
+
+    /// Load the ELF file headers from input stream. Contrary to `load`,
+    /// the section data is not read here. The reader is kept by
+    /// the sections and the data is read and cached on the first access
+    /// to it. If the data fails to be read on that access, `get_data`
+    /// returns an empty slice, `try_get_data` returns the read error,
+    /// the accessors modifying the section can't be created and `save`
+    /// fails
+    pub fn load_lazy<R: ElfioReadSeek + 'static>(&mut self, reader: R) -> Result<(), ElfioError> {
+        let reader: Rc<RefCell<dyn ElfioReadSeek>> = Rc::new(RefCell::new(reader));
+        self.load_headers(&mut *reader.borrow_mut(), None)?;
+        for section in self.sections.iter_mut() {
+            section.set_data_source(&reader);
+        }
+        for segment in self.segments.iter_mut() {
+            segment.set_data_source(&reader);
+        }
+        self.load_section_names();
+
+        Ok(())
+    }
+
+    /// Load the ELF file from a memory image of the file, for example,
+    /// a memory-mapped file. The section data is not copied, the sections
+    /// refer to the corresponding parts of the image
+    pub fn from_bytes(image: &'a [u8]) -> Result<Self, ElfioError> {
+        let mut elfio = Elfio::new();
+        let mut reader = io::Cursor::new(image);
+        elfio.load_headers(&mut reader, Some(image))?;
+        for section in elfio.sections.iter_mut() {
+            section.load_data(&mut reader)?;
+        }
+        for segment in elfio.segments.iter_mut() {
+            segment.load_data(&mut reader)?;
+        }
+        elfio.load_section_names();
+
+        Ok(elfio)
+    }
+
+    fn load_headers(
+        &mut self,
+        reader: &mut dyn ElfioReadSeek,
+        image: Option<&'a [u8]>,
+    ) -> Result<(), ElfioError> {
         let mut e_ident: [u8; constant::EI_NIDENT] = [0; constant::EI_NIDENT];
         // Read ELF file signature
-        reader.read_exact(&mut e_ident)?;
+        reader
+            .read_exact(&mut e_ident)
+            .map_err(|e| eof_to(e, ElfioError::TruncatedFileHeader))?;
         reader.seek(io::SeekFrom::Start(0))?;
 
         // Is it ELF file?
@@ -108,64 +180,128 @@ impl Elfio {
             || e_ident[constant::EI_MAG2] != constant::ELFMAG2
             || e_ident[constant::EI_MAG3] != constant::ELFMAG3
         {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "File signature doesn't conform ELF file",
-            ));
+            return Err(ElfioError::BadMagic);
         }
 
         if e_ident[constant::EI_CLASS] != constant::ELFCLASS64
             && e_ident[constant::EI_CLASS] != constant::ELFCLASS32
         {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Unknown ELF class value",
-            ));
+            return Err(ElfioError::UnsupportedClass(e_ident[constant::EI_CLASS]));
         }
 
         if e_ident[constant::EI_DATA] != constant::ELFDATA2LSB
             && e_ident[constant::EI_DATA] != constant::ELFDATA2MSB
         {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Unknown ELF file endianess",
-            ));
+            return Err(ElfioError::UnsupportedEncoding(e_ident[constant::EI_DATA]));
         }
 
+        self.converter.is_needed = !((cfg!(target_endian = "little")
+            && (e_ident[constant::EI_DATA] == constant::ELFDATA2LSB))
+            || (cfg!(target_endian = "big")
+                && (e_ident[constant::EI_DATA] == constant::ELFDATA2MSB)));
+
         if e_ident[constant::EI_CLASS] == constant::ELFCLASS64 {
-            self.header = Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new());
+            self.header = Box::new(ElfHeader::<Elf64Addr, Elf64Off>::new(
+                &self.converter,
+                e_ident[constant::EI_DATA],
+            ));
         } else {
-            self.header = Box::new(ElfHeader::<Elf32Addr, Elf32Off>::new());
+            self.header = Box::new(ElfHeader::<Elf32Addr, Elf32Off>::new(
+                &self.converter,
+                e_ident[constant::EI_DATA],
+            ));
         }
+        self.sections.clear();
+        self.segments.clear();
 
-        if (cfg!(target_endian = "little") && (e_ident[constant::EI_DATA] == constant::ELFDATA2LSB))
-            || (cfg!(target_endian = "big")
-                && (e_ident[constant::EI_DATA] == constant::ELFDATA2MSB))
-        {
-            self.converter.is_needed = false;
-        } else {
-            self.converter.is_needed = true;
+        self.header
+            .load(reader)
+            .map_err(|e| eof_to(e, ElfioError::TruncatedFileHeader))?;
+
+        self.load_sections(reader, image)?;
+        self.load_segments(reader, image)?;
+        self.map_sections_to_segments();
+
+        Ok(())
+    }
+
+    /// Save the ELF file to output stream
+    ///
+    /// File offsets of the added sections and segments are computed by
+    /// the layout pass performed before writing. Sections and segments
+    /// loaded from a file or having explicitly assigned offsets keep their
+    /// position, so saving a file which was loaded and left unmodified
+    /// reproduces the original file. A section of a loaded segment which
+    /// grew out of the segment or over the following section can't be moved,
+    /// `ElfioError::SectionOutOfSegment` is returned for it.
+    ///
+    /// The section name string table is rebuilt from the section names
+    /// when any of them was changed or added. The table is created if
+    /// the file doesn't have one yet.
+    ///
+    /// When the number of segments doesn't fit into e_phnum, it is kept in
+    /// the section 0. The section is created if the file has no sections.
+    ///
+    /// The segment data is written first. The ELF header, the program
+    /// header table and the sections are written over it, so the section
+    /// modifications take effect even if the segment data is not updated.
+    pub fn save(&mut self, writer: &mut dyn ElfioWriteSeek) -> Result<(), ElfioError> {
+        // The number of segments which doesn't fit into e_phnum is kept
+        // in the section 0, so the section header table is required
+        if self.segments.len() >= constant::PN_XNUM as usize && self.sections.is_empty() {
+            let null_section = self.create_section();
+            self.sections.push(null_section);
         }
-        self.header.set_converter(&self.converter);
 
-        match self.header.load(reader) {
-            Ok(_) => (),
-            Err(e) => return Err(e),
-        };
+        self.update_section_names();
+        self.layout()?;
+
+        for segment in &self.segments {
+            segment.save_data(writer)?;
+        }
+
+        writer.seek(io::SeekFrom::Start(0))?;
+        self.header.save(writer)?;
 
-        self.load_sections(reader)?;
-        self.load_segments(reader)?;
+        self.save_segments(writer)?;
+        self.save_sections(writer)?;
 
         Ok(())
     }
 
+    /// Add a new section to the ELF file. The mandatory null section
+    /// is created automatically in front of the first added section
+    pub fn add_section(&mut self, name: &str) -> &mut dyn ElfSectionTrait {
+        if self.sections.is_empty() {
+            let null_section = self.create_section();
+            self.sections.push(null_section);
+        }
+
+        let mut section = self.create_section();
+        section.set_index(self.sections.len() as ElfWord);
+        section.set_name(name);
+        self.sections.push(section);
+
+        let index = self.sections.len() - 1;
+        &mut *self.sections[index]
+    }
+
+    /// Add a new segment to the ELF file
+    pub fn add_segment(&mut self) -> &mut dyn ElfSegmentTrait {
+        let segment = self.create_segment();
+        self.segments.push(segment);
+
+        let index = self.segments.len() - 1;
+        &mut *self.segments[index]
+    }
+
     /// Retrieve all ELF file sections
-    pub fn get_sections(&self) -> &Vec<Box<dyn ElfSectionTrait>> {
+    pub fn get_sections(&self) -> &Vec<Box<dyn ElfSectionTrait + 'a>> {
         &self.sections
     }
 
     /// Retrieve all ELF file segments
-    pub fn get_segments(&self) -> &Vec<Box<dyn ElfSegmentTrait>> {
+    pub fn get_segments(&self) -> &Vec<Box<dyn ElfSegmentTrait + 'a>> {
         &self.segments
     }
 
@@ -181,7 +317,7 @@ impl Elfio {
     }
 
     /// Retrieve ELF file section by its index
-    pub fn get_section_by_index(&self, index: ElfHalf) -> Option<&dyn ElfSectionTrait> {
+    pub fn get_section_by_index(&self, index: ElfWord) -> Option<&dyn ElfSectionTrait> {
         let index = index as usize;
         if index < self.sections.len() {
             return Some(&*self.sections[index]);
@@ -190,76 +326,1070 @@ impl Elfio {
         None
     }
 
-    fn load_sections(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    /// Retrieve ELF file section by its name for modification
+    pub fn get_section_by_name_mut(
+        &mut self,
+        section_name: &str,
+    ) -> Option<&mut dyn ElfSectionTrait> {
+        for section in &mut self.sections {
+            if section.get_name() == section_name {
+                return Some(&mut **section);
+            }
+        }
+
+        None
+    }
+
+    /// Retrieve ELF file section by its index for modification
+    pub fn get_section_by_index_mut(&mut self, index: ElfWord) -> Option<&mut dyn ElfSectionTrait> {
+        let index = index as usize;
+        if index < self.sections.len() {
+            return Some(&mut *self.sections[index]);
+        }
+
+        None
+    }
+
+    // Reads the data of the section and of the section it is linked to,
+    // so that the accessors modifying them never replace the file content
+    // which failed to be read. Returns `None` if there is no such section
+    // or the data can't be read
+    pub(crate) fn read_section_data(&self, index: ElfWord) -> Option<()> {
+        let section = self.get_section_by_index(index)?;
+        section.try_get_data().ok()?;
+        if let Some(linked) = self.get_section_by_index(section.get_link()) {
+            linked.try_get_data().ok()?;
+        }
+
+        Some(())
+    }
+
+    /// Retrieve ELF file segment by its index
+    pub fn get_segment_by_index(&self, index: ElfWord) -> Option<&dyn ElfSegmentTrait> {
+        let index = index as usize;
+        if index < self.segments.len() {
+            return Some(&*self.segments[index]);
+        }
+
+        None
+    }
+
+    /// Retrieve ELF file segment by its index for modification
+    pub fn get_segment_by_index_mut(&mut self, index: ElfWord) -> Option<&mut dyn ElfSegmentTrait> {
+        let index = index as usize;
+        if index < self.segments.len() {
+            return Some(&mut *self.segments[index]);
+        }
+
+        None
+    }
+
+    /// Adds the section to the segment. The segment alignment is increased
+    /// up to the section alignment when necessary, and the segment file and
+    /// memory sizes are extended to cover the section. The sections without
+    /// addresses are expected to follow each other in the segment; the file
+    /// layout performed on save gives their final placement and the sizes.
+    /// Returns `false` if the segment or the section doesn't exist
+    pub fn add_section_to_segment(&mut self, segment: ElfWord, section: ElfWord) -> bool {
+        let addr_align = match self.sections.get(section as usize) {
+            Some(s) if section != 0 => s.get_addr_align(),
+            _ => return false,
+        };
+        match self.segments.get_mut(segment as usize) {
+            Some(s) => s.add_section_index(section, addr_align),
+            None => return false,
+        }
+        self.update_segment_sizes(segment as usize);
+
+        true
+    }
+
+    /// Returns indexes of the segments containing the section with
+    /// the given index
+    pub fn get_segments_for_section(&self, index: ElfWord) -> Vec<ElfWord> {
+        self.segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| segment.get_section_indices().contains(&index))
+            .map(|(i, _)| i as ElfWord)
+            .collect()
+    }
+
+    // Moves the sections to a new loadable segment following all other
+    // loadable segments in memory. The layout places the segment and
+    // the sections at the end of file. The first section gets the segment
+    // address, which is returned. The segments consisting of the moved
+    // sections only, like PT_DYNAMIC, follow them, the other segments
+    // lose them
+    pub(crate) fn move_sections_to_new_segment(
+        &mut self,
+        indices: &[ElfWord],
+    ) -> Result<Elf64Addr, ElfioError> {
+        let loads = self.segments.iter().filter(|s| s.get_type() == constant::PT_LOAD);
+        let align = loads.clone().map(|s| s.get_align()).max().filter(|a| *a > 1).unwrap_or(0x1000);
+        let mut end = 0;
+        for segment in loads {
+            end = std::cmp::max(
+                end,
+                checked_add(segment.get_virtual_address(), segment.get_memory_size())?,
+            );
+        }
+        let address = end.checked_next_multiple_of(align).ok_or(ElfioError::LayoutOverflow)?;
+
+        for segment in self.segments.iter_mut() {
+            let sections = segment.get_section_indices();
+            if segment.get_type() != constant::PT_LOAD
+                && !sections.is_empty()
+                && sections.iter().all(|index| indices.contains(index))
+            {
+                segment.reset_layout();
+                segment.set_physical_address(0);
+            } else {
+                for index in indices {
+                    segment.remove_section_index(*index);
+                }
+            }
+        }
+
+        let segment_index = self.segments.len() as ElfWord;
+        let segment = self.add_segment();
+        segment.set_type(constant::PT_LOAD);
+        segment.set_flags(constant::PF_R | constant::PF_W);
+        segment.set_virtual_address(address);
+        segment.set_physical_address(address);
+        segment.set_align(align);
+        for (i, index) in indices.iter().enumerate() {
+            if let Some(section) = self.sections.get_mut(*index as usize) {
+                section.reset_layout();
+                if i == 0 {
+                    section.set_address(address);
+                }
+                self.add_section_to_segment(segment_index, *index);
+            }
+        }
+
+        Ok(address)
+    }
+
+    fn load_sections(
+        &mut self,
+        reader: &mut dyn ElfioReadSeek,
+        image: Option<&'a [u8]>,
+    ) -> Result<(), ElfioError> {
         let entry_size = self.header.get_section_entry_size() as Elf64Off;
-        let num = self.header.get_sections_num() as Elf64Off;
+        let mut num = self.header.get_sections_num() as Elf64Off;
         let offset = self.header.get_sections_offset();
+        let file_size = reader.seek(io::SeekFrom::End(0))?;
+
+        // When the number of sections doesn't fit into e_shnum, it is zero
+        // and the real number is kept in sh_size of the section 0
+        let is_extended = num == 0 && offset != 0;
+        if is_extended {
+            num = 1;
+        }
+
+        // Shorter entries would make the headers overlap
+        let header_size = if self.get_class() == constant::ELFCLASS64 {
+            64
+        } else {
+            40
+        };
+        if num != 0 && entry_size < header_size {
+            return Err(ElfioError::UnsupportedSectionEntrySize(entry_size as ElfHalf));
+        }
+
+        let mut i = 0;
+        while i < num {
+            let index = i as ElfWord;
+            let mut section = self.create_section_in(image);
+            let header_offset = (i * entry_size)
+                .checked_add(offset)
+                .ok_or(ElfioError::TruncatedSectionHeader { index })?;
+            reader.seek(io::SeekFrom::Start(header_offset))?;
+            section
+                .load(reader)
+                .map_err(|e| eof_to(e, ElfioError::TruncatedSectionHeader { index }))?;
+            section.set_index(index);
+            if is_extended && i == 0 {
+                num = section.get_size();
+                // Don't trust the number which needs more data than the file has
+                let max_num = file_size.saturating_sub(offset) / entry_size;
+                if num > max_num {
+                    return Err(ElfioError::TruncatedSectionHeader {
+                        index: max_num as ElfWord,
+                    });
+                }
+            }
+
+            let data_size = Elfio::get_section_file_size(&*section);
+            let data_end = section.get_offset().checked_add(data_size);
+            if data_end.is_none_or(|end| end > file_size) {
+                return Err(ElfioError::SectionDataOutOfBounds {
+                    index,
+                    offset: section.get_offset(),
+                    size: section.get_size(),
+                });
+            }
 
-        for i in 0..num {
-            let mut section = self.create_section();
-            reader.seek(io::SeekFrom::Start(i * entry_size + offset))?;
-            section.load(reader)?;
             self.sections.push(section);
+            i += 1;
         }
 
-        let shstrndx = self.get_section_name_str_index();
-        if shstrndx != constant::SHN_UNDEF {
+        Ok(())
+    }
+
+    fn load_section_names(&mut self) {
+        let num = self.sections.len();
+        // Section names are left empty when the section name string table
+        // index doesn't refer to an existing section
+        let shstrndx = self.get_shstrndx();
+        if shstrndx != constant::SHN_UNDEF as ElfWord && (shstrndx as usize) < num {
             for i in 1..num {
-                let pos = self.sections[i as usize].get_name_string_offset();
+                let pos = self.sections[i].get_name_string_offset();
                 let acc = StringSectionAccessor::new(self, &*self.sections[shstrndx as usize]);
                 let name = acc.get_string(pos);
-                self.sections[i as usize].set_name(&name);
+                self.sections[i].set_name(&name);
             }
         }
+    }
 
-        Ok(())
+    fn create_section(&self) -> Box<dyn ElfSectionTrait + 'a> {
+        self.create_section_in(None)
     }
 
-    fn create_section(&self) -> Box<dyn ElfSectionTrait> {
-        let section: Box<dyn ElfSectionTrait> = if self.header.get_class() == constant::ELFCLASS64 {
-            Box::new(ElfSection::<Elf64Addr, Elf64Off, ElfXword>::new(
-                &self.converter,
-            ))
-        } else {
-            Box::new(ElfSection::<Elf32Addr, Elf32Off, ElfWord>::new(
-                &self.converter,
-            ))
-        };
+    fn create_section_in(&self, image: Option<&'a [u8]>) -> Box<dyn ElfSectionTrait + 'a> {
+        let section: Box<dyn ElfSectionTrait + 'a> =
+            if self.header.get_class() == constant::ELFCLASS64 {
+                Box::new(ElfSection::<Elf64Addr, Elf64Off, ElfXword>::new(
+                    &self.converter,
+                    image,
+                ))
+            } else {
+                Box::new(ElfSection::<Elf32Addr, Elf32Off, ElfWord>::new(
+                    &self.converter,
+                    image,
+                ))
+            };
 
         section
     }
 
-    fn load_segments(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    fn load_segments(
+        &mut self,
+        reader: &mut dyn ElfioReadSeek,
+        image: Option<&'a [u8]>,
+    ) -> Result<(), ElfioError> {
         let entry_size = self.header.get_segment_entry_size() as Elf64Off;
-        let num = self.header.get_segments_num() as Elf64Off;
+        let num = self.get_segments_table_num() as Elf64Off;
         let offset = self.header.get_segments_offset();
+        let file_size = reader.seek(io::SeekFrom::End(0))?;
+
+        // Shorter entries would make the headers overlap
+        let header_size = if self.get_class() == constant::ELFCLASS64 {
+            56
+        } else {
+            32
+        };
+        if num != 0 && entry_size < header_size {
+            return Err(ElfioError::UnsupportedSegmentEntrySize(entry_size as ElfHalf));
+        }
+        // Don't trust the number which needs more data than the file has
+        let max_num = file_size.saturating_sub(offset) / std::cmp::max(entry_size, 1);
+        if num > max_num {
+            return Err(ElfioError::TruncatedSegmentHeader {
+                index: max_num as ElfWord,
+            });
+        }
 
         for i in 0..num {
-            let mut segment = self.create_segment();
-            reader.seek(io::SeekFrom::Start(i * entry_size + offset))?;
-            segment.load(reader)?;
+            let mut segment = self.create_segment_in(image);
+            let index = i as ElfWord;
+            let header_offset = (i * entry_size)
+                .checked_add(offset)
+                .ok_or(ElfioError::TruncatedSegmentHeader { index })?;
+            reader.seek(io::SeekFrom::Start(header_offset))?;
+            segment
+                .load(reader)
+                .map_err(|e| eof_to(e, ElfioError::TruncatedSegmentHeader { index }))?;
+
+            let data_end = segment.get_offset().checked_add(segment.get_file_size());
+            if data_end.is_none_or(|end| end > file_size) {
+                return Err(ElfioError::SegmentDataOutOfBounds {
+                    index,
+                    offset: segment.get_offset(),
+                    size: segment.get_file_size(),
+                });
+            }
+
             self.segments.push(segment);
         }
 
         Ok(())
     }
 
-    fn create_segment(&self) -> Box<dyn ElfSegmentTrait> {
-        let segment: Box<dyn ElfSegmentTrait> = if self.header.get_class() == constant::ELFCLASS64 {
-            Box::new(ElfSegment::<Elf64Addr, Elf64Off, ElfXword>::new(
-                &self.converter,
-                self.header.get_class(),
-            ))
+    // Finds the sections each loaded segment contains. The rules are
+    // the same as used by `readelf -l` for its section to segment mapping
+    fn map_sections_to_segments(&mut self) {
+        for segment in self.segments.iter_mut() {
+            for section in self.sections.iter().skip(1) {
+                if Self::is_section_in_segment(&**section, &**segment) {
+                    segment.add_section_index(section.get_index(), 0);
+                }
+            }
+            segment.set_layout_done();
+        }
+    }
+
+    // Checks whether the section data and memory are covered by the segment
+    fn is_section_within_segment(
+        section: &dyn ElfSectionTrait,
+        segment: &dyn ElfSegmentTrait,
+    ) -> bool {
+        let is_tls = section.get_flags() & constant::SHF_TLS != 0;
+        let is_alloc = section.get_flags() & constant::SHF_ALLOC != 0;
+        let is_nobits = section.get_type() == constant::SHT_NOBITS;
+        let size = section.get_size();
+        let within = |start: u64, base: u64, limit: u64| {
+            start >= base && (start - base).checked_add(size).is_some_and(|end| end <= limit)
+        };
+
+        (is_nobits || within(section.get_offset(), segment.get_offset(), segment.get_file_size()))
+            && (!is_alloc
+                // .tbss occupies no memory outside of PT_TLS segment
+                || (is_tls && is_nobits && segment.get_type() != constant::PT_TLS)
+                || within(
+                    section.get_address(),
+                    segment.get_virtual_address(),
+                    segment.get_memory_size(),
+                ))
+    }
+
+    fn is_section_in_segment(section: &dyn ElfSectionTrait, segment: &dyn ElfSegmentTrait) -> bool {
+        let p_type = segment.get_type();
+        let is_tls = section.get_flags() & constant::SHF_TLS != 0;
+        let is_alloc = section.get_flags() & constant::SHF_ALLOC != 0;
+        let is_nobits = section.get_type() == constant::SHT_NOBITS;
+
+        // Only PT_LOAD, PT_GNU_RELRO and PT_TLS segments contain TLS sections.
+        // PT_TLS segment contains nothing else, PT_PHDR contains no sections
+        let type_matches = if is_tls {
+            p_type == constant::PT_TLS
+                || p_type == constant::PT_GNU_RELRO
+                || p_type == constant::PT_LOAD
         } else {
-            Box::new(ElfSegment::<Elf32Addr, Elf32Off, ElfWord>::new(
-                &self.converter,
-                self.header.get_class(),
-            ))
+            p_type != constant::PT_TLS && p_type != constant::PT_PHDR
         };
+        // Loadable segments contain allocatable sections only
+        let alloc_matches = is_alloc
+            || !(p_type == constant::PT_LOAD
+                || p_type == constant::PT_DYNAMIC
+                || p_type == constant::PT_GNU_EH_FRAME
+                || p_type == constant::PT_GNU_STACK
+                || p_type == constant::PT_GNU_RELRO);
+        if !type_matches || !alloc_matches {
+            return false;
+        }
+
+        // .tbss occupies no memory outside of PT_TLS segment
+        if is_tls && is_nobits && p_type != constant::PT_TLS {
+            return false;
+        }
+        let size = section.get_size();
+
+        // Sections having data are placed by their file offset
+        let offset = section.get_offset();
+        let p_offset = segment.get_offset();
+        let file_size = segment.get_file_size();
+        let in_file = is_nobits
+            || (offset >= p_offset
+                && offset - p_offset <= file_size.wrapping_sub(1)
+                && (offset - p_offset).checked_add(size).is_some_and(|end| end <= file_size));
+
+        // Allocatable sections are placed by their address
+        let address = section.get_address();
+        let vaddr = segment.get_virtual_address();
+        let memory_size = segment.get_memory_size();
+        let in_memory = !is_alloc
+            || (address >= vaddr
+                && address - vaddr <= memory_size.wrapping_sub(1)
+                && (address - vaddr).checked_add(size).is_some_and(|end| end <= memory_size));
+        if !in_file || !in_memory {
+            return false;
+        }
+
+        // Empty sections at the segment boundaries don't belong to
+        // PT_DYNAMIC and PT_NOTE segments
+        (p_type != constant::PT_DYNAMIC && p_type != constant::PT_NOTE)
+            || size != 0
+            || memory_size == 0
+            || ((is_nobits || (offset > p_offset && offset - p_offset < file_size))
+                && (!is_alloc || (address > vaddr && address - vaddr < memory_size)))
+    }
+
+    fn create_segment(&self) -> Box<dyn ElfSegmentTrait + 'a> {
+        self.create_segment_in(None)
+    }
+
+    fn create_segment_in(&self, image: Option<&'a [u8]>) -> Box<dyn ElfSegmentTrait + 'a> {
+        let segment: Box<dyn ElfSegmentTrait + 'a> =
+            if self.header.get_class() == constant::ELFCLASS64 {
+                Box::new(ElfSegment::<Elf64Addr, Elf64Off, ElfXword>::new(
+                    &self.converter,
+                    self.header.get_class(),
+                    image,
+                ))
+            } else {
+                Box::new(ElfSegment::<Elf32Addr, Elf32Off, ElfWord>::new(
+                    &self.converter,
+                    self.header.get_class(),
+                    image,
+                ))
+            };
 
         segment
     }
 
+    // Rebuilds the section name string table and the name offsets if any
+    // section name can't be found at its offset in the current table
+    fn update_section_names(&mut self) {
+        if self.sections.is_empty() {
+            return;
+        }
+
+        let mut shstrndx = self.get_shstrndx() as usize;
+        if shstrndx == constant::SHN_UNDEF as usize
+            || shstrndx >= self.sections.len()
+            || self.sections[shstrndx].get_type() != constant::SHT_STRTAB
+        {
+            shstrndx = match self.sections.iter().position(|section| {
+                section.get_type() == constant::SHT_STRTAB && section.get_name() == ".shstrtab"
+            }) {
+                Some(index) => index,
+                None => {
+                    let section = self.add_section(".shstrtab");
+                    section.set_type(constant::SHT_STRTAB);
+                    section.set_addr_align(1);
+                    section.get_index() as usize
+                }
+            };
+            self.set_shstrndx(shstrndx as ElfWord);
+        }
+
+        let acc = StringSectionAccessor::new(self, &*self.sections[shstrndx]);
+        let up_to_date = self.sections.iter().all(|section| {
+            acc.get_string(section.get_name_string_offset()) == section.get_name()
+        });
+        if up_to_date {
+            return;
+        }
+
+        // Equal names and names which are suffixes of already added ones
+        // share the table entries. All suffixes of the added names are
+        // remembered, so files with many sections are handled quickly
+        let names: Vec<String> = self
+            .sections
+            .iter()
+            .map(|section| section.get_name().to_string())
+            .collect();
+        let mut data = vec![0u8];
+        let mut suffixes: HashMap<&[u8], ElfWord> = HashMap::new();
+        suffixes.insert(&[], 0);
+        let mut offsets = Vec::with_capacity(names.len());
+        for name in &names {
+            let name = name.as_bytes();
+            let offset = match suffixes.get(name) {
+                Some(offset) => *offset,
+                None => {
+                    let offset = data.len() as ElfWord;
+                    data.extend_from_slice(name);
+                    data.push(0);
+                    for pos in 0..name.len() {
+                        suffixes.entry(&name[pos..]).or_insert(offset + pos as ElfWord);
+                    }
+                    offset
+                }
+            };
+            offsets.push(offset);
+        }
+
+        self.sections[shstrndx].set_data(&data);
+        for (section, offset) in self.sections.iter_mut().zip(offsets) {
+            section.set_name_string_offset(offset);
+        }
+    }
+
+    // Returns the section name string table index. When the index doesn't
+    // fit into e_shstrndx, it is SHN_XINDEX and the real index is kept in
+    // sh_link of the section 0
+    fn get_shstrndx(&self) -> ElfWord {
+        let shstrndx = self.get_section_name_str_index();
+        match self.sections.first() {
+            Some(section) if shstrndx == constant::SHN_XINDEX => section.get_link(),
+            _ => shstrndx as ElfWord,
+        }
+    }
+
+    fn set_shstrndx(&mut self, shstrndx: ElfWord) {
+        if shstrndx >= constant::SHN_LORESERVE as ElfWord {
+            self.set_section_name_str_index(constant::SHN_XINDEX);
+            self.sections[0].set_link(shstrndx);
+        } else {
+            if self.get_section_name_str_index() == constant::SHN_XINDEX {
+                self.sections[0].set_link(0);
+            }
+            self.set_section_name_str_index(shstrndx as ElfHalf);
+        }
+    }
+
+    // Returns the number of entries in the section header table. When the
+    // number doesn't fit into e_shnum, it is kept in sh_size of the section 0
+    fn get_sections_table_num(&self) -> usize {
+        match self.sections.first() {
+            Some(section) if self.get_sections_num() == 0 && self.get_sections_offset() != 0 => {
+                section.get_size() as usize
+            }
+            _ => self.get_sections_num() as usize,
+        }
+    }
+
+    // Returns the number of entries in the program header table. When
+    // the number doesn't fit into e_phnum, it is PN_XNUM and the real number
+    // is kept in sh_info of the section 0
+    fn get_segments_table_num(&self) -> usize {
+        match self.sections.first() {
+            Some(section) if self.get_segments_num() == constant::PN_XNUM => {
+                section.get_info() as usize
+            }
+            _ => self.get_segments_num() as usize,
+        }
+    }
+
+    fn set_segments_table_num(&mut self, num: usize) {
+        if num >= constant::PN_XNUM as usize {
+            self.sections[0].set_info(num as ElfWord);
+            self.header.set_segments_num(constant::PN_XNUM);
+        } else {
+            // Drop the number of segments kept by a file with many segments
+            if self.get_segments_num() == constant::PN_XNUM && !self.sections.is_empty() {
+                self.sections[0].set_info(0);
+            }
+            self.header.set_segments_num(num as ElfHalf);
+        }
+    }
+
+    // Assigns file offsets to everything which doesn't have them yet.
+    // The ELF header, the program header table, the segments and their
+    // sections go first; the sections not belonging to any segment and
+    // the section header table are appended after them
+    fn layout(&mut self) -> Result<(), ElfioError> {
+        let table_align: Elf64Off = if self.get_class() == constant::ELFCLASS64 {
+            8
+        } else {
+            4
+        };
+        let mut occupied = vec![FileRange {
+            start: 0,
+            end:   self.get_header_size() as Elf64Off,
+            owner: None,
+        }];
+
+        for section in &self.sections {
+            let size = Self::get_section_file_size(&**section);
+            if section.is_offset_set() && size != 0 {
+                occupied.push(FileRange {
+                    start: section.get_offset(),
+                    end:   section.get_offset().saturating_add(size),
+                    owner: Some(section.get_index() as usize),
+                });
+            }
+        }
+
+        // The data of segments without sections, core dump memory images,
+        // for example, is kept in place as well
+        for segment in &self.segments {
+            if segment.is_offset_set()
+                && segment.get_type() != constant::PT_PHDR
+                && segment.get_section_indices().is_empty()
+                && segment.get_file_size() != 0
+            {
+                occupied.push(FileRange {
+                    start: segment.get_offset(),
+                    end:   segment.get_offset().saturating_add(segment.get_file_size()),
+                    owner: None,
+                });
+            }
+        }
+
+        // Section header table stays in place if the number of entries
+        // didn't change since the file was loaded or last saved
+        let sections_table_size =
+            self.sections.len() as Elf64Off * self.get_section_entry_size() as Elf64Off;
+        let keep_sections_table = !self.sections.is_empty()
+            && self.get_sections_offset() != 0
+            && self.get_sections_table_num() == self.sections.len();
+        if keep_sections_table {
+            occupied.push(FileRange {
+                start: self.get_sections_offset(),
+                end:   self.get_sections_offset().saturating_add(sections_table_size),
+                owner: None,
+            });
+        }
+
+        // Program header table. The table moved from its place in a file
+        // having PT_PHDR segment has to be loaded, it follows the sections
+        // of a new loadable segment then
+        let segments_table_size = self.get_segments_table_size();
+        let mut table_host = None;
+        if self.segments.is_empty() {
+            self.set_segments_offset(0);
+        } else if self.get_segments_offset() == 0
+            || self.get_segments_table_num() != self.segments.len()
+        {
+            let mut offset = align_up(self.get_header_size() as Elf64Off, table_align);
+            let end = checked_add(offset, segments_table_size)?;
+            if FileRange::overlaps(&occupied, offset, end, None) {
+                offset = align_up(FileRange::end_of(&occupied), table_align);
+                table_host = self.find_segments_table_host();
+            }
+            self.set_segments_offset(offset);
+        }
+        if !self.segments.is_empty() && table_host.is_none() {
+            occupied.push(FileRange {
+                start: self.get_segments_offset(),
+                end:   self.get_segments_offset().saturating_add(segments_table_size),
+                owner: None,
+            });
+        }
+        self.set_segments_table_num(self.segments.len());
+
+        // Segments and the sections they contain. The sections of segments
+        // which are already placed are handled as the rest of sections.
+        // Loadable segments go first, so the other ones, like PT_DYNAMIC or
+        // PT_GNU_RELRO, find their sections placed
+        let mut in_segment = vec![false; self.sections.len()];
+        let is_load = |i: &usize| self.segments[*i].get_type() == constant::PT_LOAD;
+        let order: Vec<usize> = (0..self.segments.len())
+            .filter(is_load)
+            .chain((0..self.segments.len()).filter(|i| !is_load(i)))
+            .collect();
+        let mut placed = Vec::new();
+        for i in order {
+            if !self.segment_needs_layout(i) {
+                placed.push(i);
+                continue;
+            }
+            for index in self.segments[i].get_section_indices() {
+                if (*index as usize) < in_segment.len() {
+                    in_segment[*index as usize] = true;
+                }
+            }
+            self.layout_segment(i, &mut occupied)?;
+            if table_host == Some(i) {
+                self.place_segments_table(i, table_align, &mut occupied)?;
+            }
+        }
+
+        // The sections of segments kept in place can't be moved. A section
+        // grown out of its segment or over the next one would be loaded
+        // partially
+        let mut kept_in = vec![None; self.sections.len()];
+        for i in placed {
+            let segment = &*self.segments[i];
+            for index in self.get_segment_sections(i) {
+                if !Self::is_section_within_segment(&*self.sections[index], segment) {
+                    return Err(ElfioError::SectionOutOfSegment {
+                        section: index as ElfWord,
+                        segment: i as ElfWord,
+                    });
+                }
+                kept_in[index] = Some(i);
+            }
+        }
+
+        // Sections which don't belong to any segment. A loaded section
+        // is moved to the end of file only when it overlaps anything else
+        for (i, section) in self.sections.iter_mut().enumerate() {
+            if in_segment[i] || section.get_type() == constant::SHT_NULL {
+                continue;
+            }
+
+            let size = Self::get_section_file_size(&**section);
+            if section.is_offset_set()
+                && (size == 0
+                    || !FileRange::overlaps(
+                        &occupied,
+                        section.get_offset(),
+                        section.get_offset().saturating_add(size),
+                        Some(i),
+                    ))
+            {
+                continue;
+            }
+            if let Some(segment) = kept_in[i] {
+                return Err(ElfioError::SectionOutOfSegment {
+                    section: i as ElfWord,
+                    segment: segment as ElfWord,
+                });
+            }
+
+            occupied.retain(|range| range.owner != Some(i));
+            let offset = align_up(FileRange::end_of(&occupied), section.get_addr_align());
+            section.set_offset(offset);
+            if size != 0 {
+                occupied.push(FileRange {
+                    start: offset,
+                    end:   offset.saturating_add(size),
+                    owner: Some(i),
+                });
+            }
+        }
+
+        // Section header table
+        if self.sections.is_empty() {
+            self.set_sections_offset(0);
+        } else if !keep_sections_table {
+            let offset = align_up(FileRange::end_of(&occupied), table_align);
+            self.set_sections_offset(offset);
+        }
+        let num = self.sections.len();
+        if num >= constant::SHN_LORESERVE as usize {
+            self.sections[0].set_size(num as ElfXword);
+            self.header.set_sections_num(0);
+        } else {
+            // Drop the number of sections kept by a file with many sections
+            if num != 0 && self.get_sections_num() == 0 {
+                self.sections[0].set_size(0);
+            }
+            self.header.set_sections_num(num as ElfHalf);
+        }
+
+        Ok(())
+    }
+
+    // Returns indexes of the existing sections belonging to the segment
+    fn get_segment_sections(&self, index: usize) -> Vec<usize> {
+        self.segments[index]
+            .get_section_indices()
+            .iter()
+            .map(|index| *index as usize)
+            .filter(|index| *index < self.sections.len())
+            .collect()
+    }
+
+    // Extends the segment sizes to cover its sections. The sections without
+    // addresses are placed one after another like the layout does
+    fn update_segment_sizes(&mut self, index: usize) {
+        let segment = &self.segments[index];
+        let vaddr = segment.get_virtual_address();
+        let is_tls_segment = segment.get_type() == constant::PT_TLS;
+        let file_size = segment.get_file_size();
+        let memory_size = segment.get_memory_size();
+        let mut file_end = 0;
+        let mut memory_end = 0;
+        for i in self.get_segment_sections(index) {
+            let section = &self.sections[i];
+            let is_nobits = section.get_type() == constant::SHT_NOBITS;
+            let is_tbss = is_nobits && section.get_flags() & constant::SHF_TLS != 0;
+            let address = section.get_address();
+            let start = if section.is_address_set() && address >= vaddr {
+                address - vaddr
+            } else {
+                align_up(memory_end, section.get_addr_align())
+            };
+
+            let end = start.saturating_add(section.get_size());
+            if !is_nobits {
+                file_end = std::cmp::max(file_end, end);
+            }
+            if !is_tbss || is_tls_segment {
+                memory_end = std::cmp::max(memory_end, end);
+            }
+        }
+
+        // Adding a section never shrinks the segment
+        let file_size = std::cmp::max(file_size, file_end);
+        let segment = &mut self.segments[index];
+        segment.set_file_size(file_size);
+        segment.set_memory_size(std::cmp::max(memory_size, std::cmp::max(file_size, memory_end)));
+    }
+
+    // A segment is laid out when it was added or got new sections.
+    // PT_PHDR follows the program header table
+    fn segment_needs_layout(&self, index: usize) -> bool {
+        let segment = &self.segments[index];
+        if segment.get_type() == constant::PT_PHDR {
+            return segment.get_offset() != self.get_segments_offset()
+                || segment.get_file_size() != self.get_segments_table_size()
+                || segment.is_layout_needed();
+        }
+
+        segment.is_layout_needed()
+            || self
+                .get_segment_sections(index)
+                .iter()
+                .any(|i| !self.sections[*i].is_offset_set())
+    }
+
+    // Places a segment, which was added or got new sections, right after
+    // the data laid out so far. The segment offset is kept congruent with
+    // its virtual address modulo the segment alignment
+    fn layout_segment(
+        &mut self,
+        index: usize,
+        occupied: &mut Vec<FileRange>,
+    ) -> Result<(), ElfioError> {
+        let indices = self.get_segment_sections(index);
+        if self.segments[index].get_type() == constant::PT_PHDR {
+            return self.layout_phdr_segment(index);
+        }
+
+        let placed = indices
+            .iter()
+            .filter(|i| self.sections[**i].is_offset_set())
+            .map(|i| self.sections[*i].get_offset())
+            .min();
+        let placed_address = indices
+            .iter()
+            .filter(|i| self.sections[**i].is_offset_set())
+            .map(|i| self.sections[*i].get_address())
+            .min();
+
+        let segment = &mut self.segments[index];
+        // A segment sharing sections with a loadable segment gets its
+        // address from them
+        if let (false, Some(address)) = (segment.is_address_set(), placed_address) {
+            segment.set_virtual_address(address);
+            if segment.get_physical_address() == 0 {
+                segment.set_physical_address(address);
+            }
+        }
+        segment.set_layout_done();
+
+        let vaddr = segment.get_virtual_address();
+        let align = std::cmp::max(segment.get_align(), 1);
+        if indices.is_empty() {
+            // A segment without sections is placed by its own data
+            let size = segment.get_file_size();
+            let mut offset = segment.get_offset();
+            if size != 0 {
+                offset = align_congruent(FileRange::end_of(occupied), vaddr, align)?;
+                occupied.push(FileRange {
+                    start: offset,
+                    end:   checked_add(offset, size)?,
+                    owner: None,
+                });
+            }
+            segment.set_offset(offset);
+            return Ok(());
+        }
+
+        let segment_offset = if segment.is_offset_set() {
+            segment.get_offset()
+        } else if let Some(offset) = placed {
+            offset
+        } else {
+            align_congruent(FileRange::end_of(occupied), vaddr, align)?
+        };
+
+        // TLS sections without data take no space in the loadable segment
+        let is_tls_segment = segment.get_type() == constant::PT_TLS;
+        let mut file_pos = segment_offset;
+        let mut file_end = segment_offset;
+        let mut memory_end = vaddr;
+        for i in indices {
+            let section = &mut self.sections[i];
+            let is_nobits = section.get_type() == constant::SHT_NOBITS;
+            let is_tbss = is_nobits && section.get_flags() & constant::SHF_TLS != 0;
+            let addr_align = section.get_addr_align();
+
+            if !section.is_offset_set() {
+                let mut offset = align_up(file_pos, addr_align);
+                if is_nobits {
+                    if !section.is_address_set() {
+                        let address = checked_add(vaddr, offset - segment_offset)?;
+                        let address = std::cmp::max(memory_end, address);
+                        section.set_address(align_up(address, addr_align));
+                    }
+                } else if section.is_address_set() {
+                    let address = section.get_address();
+                    if address >= vaddr {
+                        let address_offset = checked_add(segment_offset, address - vaddr)?;
+                        if address_offset >= file_pos {
+                            offset = address_offset;
+                        }
+                    }
+                } else {
+                    // The data already placed inside of the segment, the file
+                    // header for the segment starting at the file beginning,
+                    // for example, is skipped
+                    let memory_offset = checked_add(segment_offset, memory_end - vaddr)?;
+                    offset = align_up(std::cmp::max(offset, memory_offset), addr_align);
+                    let size = Self::get_section_file_size(&**section);
+                    while let Some(end) = FileRange::find_overlap(
+                        occupied,
+                        offset,
+                        checked_add(offset, size)?,
+                        Some(i),
+                    ) {
+                        offset = align_up(end, addr_align);
+                    }
+                    section.set_address(checked_add(vaddr, offset - segment_offset)?);
+                }
+
+                section.set_offset(offset);
+                let size = Self::get_section_file_size(&**section);
+                if size != 0 {
+                    occupied.push(FileRange {
+                        start: offset,
+                        end:   checked_add(offset, size)?,
+                        owner: Some(i),
+                    });
+                }
+            }
+
+            let size = Self::get_section_file_size(&**section);
+            if size != 0 {
+                let end = checked_add(section.get_offset(), size)?;
+                file_pos = std::cmp::max(file_pos, end);
+                file_end = std::cmp::max(file_end, end);
+            }
+            if !is_tbss || is_tls_segment {
+                let end = checked_add(section.get_address(), section.get_size())?;
+                memory_end = std::cmp::max(memory_end, end);
+            }
+        }
+
+        let segment = &mut self.segments[index];
+        let file_size = file_end - segment_offset;
+        segment.set_offset(segment_offset);
+        segment.set_file_size(file_size);
+        segment.set_memory_size(std::cmp::max(file_size, memory_end - vaddr));
+
+        Ok(())
+    }
+
+    // Returns a new loadable segment with the address assigned to place
+    // the program header table into, if the file has PT_PHDR segment
+    fn find_segments_table_host(&self) -> Option<usize> {
+        if !self.segments.iter().any(|s| s.get_type() == constant::PT_PHDR) {
+            return None;
+        }
+
+        self.segments.iter().position(|segment| {
+            segment.get_type() == constant::PT_LOAD
+                && !segment.is_offset_set()
+                && segment.is_address_set()
+        })
+    }
+
+    // Places the program header table right after the data of the loadable
+    // segment, which is extended to cover the table. The table goes to
+    // the end of file if the segment has memory not backed by the file
+    fn place_segments_table(
+        &mut self,
+        index: usize,
+        table_align: ElfXword,
+        occupied: &mut Vec<FileRange>,
+    ) -> Result<(), ElfioError> {
+        let size = self.get_segments_table_size();
+        let segment = &mut self.segments[index];
+        let segment_offset = segment.get_offset();
+        let segment_end = checked_add(segment_offset, segment.get_file_size())?;
+        let mut offset = align_up(segment_end, table_align);
+        let mut end = checked_add(offset, size)?;
+        if segment.get_memory_size() > segment.get_file_size()
+            || FileRange::overlaps(occupied, offset, end, None)
+        {
+            offset = align_up(FileRange::end_of(occupied), table_align);
+            end = checked_add(offset, size)?;
+        } else {
+            segment.set_file_size(end - segment_offset);
+            segment.set_memory_size(end - segment_offset);
+        }
+
+        self.set_segments_offset(offset);
+        occupied.push(FileRange {
+            start: offset,
+            end,
+            owner: None,
+        });
+
+        Ok(())
+    }
+
+    // PT_PHDR segment describes the program header table. Its address is
+    // taken from the loadable segment containing the table, if any
+    fn layout_phdr_segment(&mut self, index: usize) -> Result<(), ElfioError> {
+        let offset = self.get_segments_offset();
+        let size = self.get_segments_table_size();
+        let end = checked_add(offset, size)?;
+        let address = match self.segments.iter().find(|segment| {
+            segment.get_type() == constant::PT_LOAD
+                && segment.get_offset() <= offset
+                && segment
+                    .get_offset()
+                    .checked_add(segment.get_file_size())
+                    .is_some_and(|segment_end| end <= segment_end)
+        }) {
+            Some(segment) => Some(checked_add(
+                segment.get_virtual_address(),
+                offset - segment.get_offset(),
+            )?),
+            None => None,
+        };
+
+        let segment = &mut self.segments[index];
+        segment.set_offset(offset);
+        segment.set_file_size(size);
+        segment.set_memory_size(size);
+        if let Some(address) = address {
+            segment.set_virtual_address(address);
+            segment.set_physical_address(address);
+        }
+        segment.set_layout_done();
+
+        Ok(())
+    }
+
+    // Returns the size of the program header table
+    fn get_segments_table_size(&self) -> ElfXword {
+        self.segments.len() as ElfXword * self.get_segment_entry_size() as ElfXword
+    }
+
+    // Returns number of bytes the section occupies in the file
+    fn get_section_file_size(section: &dyn ElfSectionTrait) -> Elf64Off {
+        if section.get_type() == constant::SHT_NULL || section.get_type() == constant::SHT_NOBITS {
+            0
+        } else {
+            section.get_size()
+        }
+    }
+
+    fn save_sections(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        let entry_size = self.header.get_section_entry_size() as Elf64Off;
+        let offset = self.header.get_sections_offset();
+
+        for (i, section) in self.sections.iter().enumerate() {
+            writer.seek(io::SeekFrom::Start(i as Elf64Off * entry_size + offset))?;
+            section.save(writer)?;
+            section.save_data(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn save_segments(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        let entry_size = self.header.get_segment_entry_size() as Elf64Off;
+        let offset = self.header.get_segments_offset();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            writer.seek(io::SeekFrom::Start(i as Elf64Off * entry_size + offset))?;
+            segment.save(writer)?;
+        }
+
+        Ok(())
+    }
+
     ELFIO_HEADER_ACCESS_GET!(u8, class);
     ELFIO_HEADER_ACCESS_GET!(u8, elf_version);
     ELFIO_HEADER_ACCESS_GET!(u8, encoding);
@@ -281,7 +1411,92 @@ impl Elfio {
     ELFIO_HEADER_ACCESS_GET_SET!(ElfHalf, section_name_str_index);
 }
 
-impl std::fmt::Debug for Elfio {
+// --------------------------------------------------------------------------
+// A file area occupied by the ELF header, a table or a section
+struct FileRange {
+    start: Elf64Off,
+    end:   Elf64Off,
+    owner: Option<usize>,
+}
+
+impl FileRange {
+    // Checks whether the area intersects any of the ranges except
+    // the ones belonging to the given section
+    fn overlaps(
+        ranges: &[FileRange],
+        start: Elf64Off,
+        end: Elf64Off,
+        owner: Option<usize>,
+    ) -> bool {
+        ranges.iter().any(|range| {
+            (owner.is_none() || range.owner != owner) && start < range.end && range.start < end
+        })
+    }
+
+    // Returns the end of a range intersecting the area except the ones
+    // belonging to the given section
+    fn find_overlap(
+        ranges: &[FileRange],
+        start: Elf64Off,
+        end: Elf64Off,
+        owner: Option<usize>,
+    ) -> Option<Elf64Off> {
+        ranges
+            .iter()
+            .find(|range| {
+                (owner.is_none() || range.owner != owner) && start < range.end && range.start < end
+            })
+            .map(|range| range.end)
+    }
+
+    // Returns the first file offset following all ranges
+    fn end_of(ranges: &[FileRange]) -> Elf64Off {
+        ranges.iter().map(|range| range.end).max().unwrap_or(0)
+    }
+}
+
+// --------------------------------------------------------------------------
+// Adds file offsets, addresses or sizes of the layout
+fn checked_add(value: u64, addend: u64) -> Result<u64, ElfioError> {
+    value.checked_add(addend).ok_or(ElfioError::LayoutOverflow)
+}
+
+// Returns the first offset not less than `offset` which is congruent with
+// the address modulo the alignment
+fn align_congruent(
+    offset: Elf64Off,
+    address: Elf64Addr,
+    align: ElfXword,
+) -> Result<Elf64Off, ElfioError> {
+    let (address, remainder) = (address % align, offset % align);
+    let padding = if address >= remainder {
+        address - remainder
+    } else {
+        align - (remainder - address)
+    };
+    checked_add(offset, padding)
+}
+
+// --------------------------------------------------------------------------
+fn align_up(value: Elf64Off, align: ElfXword) -> Elf64Off {
+    if align > 1 {
+        value.div_ceil(align).saturating_mul(align)
+    } else {
+        value
+    }
+}
+
+// --------------------------------------------------------------------------
+// Reports an unexpected end of the input stream as the given error
+fn eof_to(e: io::Error, error: ElfioError) -> ElfioError {
+    if e.kind() == io::ErrorKind::UnexpectedEof {
+        error
+    } else {
+        ElfioError::Io(e)
+    }
+}
+
+impl std::fmt::Debug for Elfio<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Elfio")
             //  .field("x", &self.x)
@@ -290,7 +1505,7 @@ impl std::fmt::Debug for Elfio {
     }
 }
 
-impl Default for Elfio {
+impl Default for Elfio<'_> {
     fn default() -> Self {
         Self::new()
     }
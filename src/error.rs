@@ -0,0 +1,197 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+use std::error;
+use std::fmt;
+use std::io;
+
+use super::types::*;
+
+// --------------------------------------------------------------------------
+/// Errors reported while loading or saving ELF files
+#[derive(Debug)]
+pub enum ElfioError {
+    /// An I/O error reported by the underlying reader or writer
+    Io(io::Error),
+    /// The file doesn't start with the ELF signature
+    BadMagic,
+    /// The ELF class value in `e_ident` is neither 32 nor 64-bit
+    UnsupportedClass(u8),
+    /// The ELF data encoding value in `e_ident` is neither LSB nor MSB
+    UnsupportedEncoding(u8),
+    /// The ELF file header is shorter than required by its class
+    TruncatedFileHeader,
+    /// The section header table entry size is smaller than the section header
+    UnsupportedSectionEntrySize(ElfHalf),
+    /// The section header table entry goes past the end of the file
+    TruncatedSectionHeader {
+        /// Index of the section
+        index: ElfWord,
+    },
+    /// The program header table entry size is smaller than the program header
+    UnsupportedSegmentEntrySize(ElfHalf),
+    /// The program header table entry goes past the end of the file
+    TruncatedSegmentHeader {
+        /// Index of the segment
+        index: ElfWord,
+    },
+    /// The section data goes past the end of the file
+    SectionDataOutOfBounds {
+        /// Index of the section
+        index:  ElfWord,
+        /// File offset of the section data
+        offset: Elf64Off,
+        /// Size of the section data
+        size:   ElfXword,
+    },
+    /// The segment data goes past the end of the file
+    SegmentDataOutOfBounds {
+        /// Index of the segment
+        index:  ElfWord,
+        /// File offset of the segment data
+        offset: Elf64Off,
+        /// Size of the segment data
+        size:   ElfXword,
+    },
+    /// The file type can't be loaded to memory for execution
+    UnsupportedFileType(ElfHalf),
+    /// The segment memory range doesn't fit the address space
+    SegmentAddressOutOfRange {
+        /// Index of the segment
+        index:   ElfWord,
+        /// Virtual address of the segment
+        address: Elf64Addr,
+        /// Size of the segment in memory
+        size:    ElfXword,
+    },
+    /// The section doesn't fit into the segment it belongs to anymore, and
+    /// the segment is kept in place
+    SectionOutOfSegment {
+        /// Index of the section
+        section: ElfWord,
+        /// Index of the segment
+        segment: ElfWord,
+    },
+    /// The file offsets or the addresses computed by the layout don't fit
+    /// into 64 bits
+    LayoutOverflow,
+    /// The section link doesn't refer to a string table
+    MissingStringTable {
+        /// Index of the section
+        section: ElfWord,
+    },
+}
+
+// --------------------------------------------------------------------------
+impl fmt::Display for ElfioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElfioError::Io(e) => write!(f, "I/O error: {}", e),
+            ElfioError::BadMagic => write!(f, "File signature doesn't conform ELF file"),
+            ElfioError::UnsupportedClass(class) => {
+                write!(f, "Unknown ELF class value {}", class)
+            }
+            ElfioError::UnsupportedEncoding(encoding) => {
+                write!(f, "Unknown ELF file endianess {}", encoding)
+            }
+            ElfioError::TruncatedFileHeader => write!(f, "ELF file header is truncated"),
+            ElfioError::UnsupportedSectionEntrySize(size) => {
+                write!(f, "Section header table entry size {} is too small", size)
+            }
+            ElfioError::TruncatedSectionHeader { index } => {
+                write!(f, "Header of section {} is truncated", index)
+            }
+            ElfioError::UnsupportedSegmentEntrySize(size) => {
+                write!(f, "Program header table entry size {} is too small", size)
+            }
+            ElfioError::TruncatedSegmentHeader { index } => {
+                write!(f, "Header of segment {} is truncated", index)
+            }
+            ElfioError::SectionDataOutOfBounds {
+                index,
+                offset,
+                size,
+            } => write!(
+                f,
+                "Data of section {} (offset 0x{:x}, size 0x{:x}) is out of file bounds",
+                index, offset, size
+            ),
+            ElfioError::SegmentDataOutOfBounds {
+                index,
+                offset,
+                size,
+            } => write!(
+                f,
+                "Data of segment {} (offset 0x{:x}, size 0x{:x}) is out of file bounds",
+                index, offset, size
+            ),
+            ElfioError::UnsupportedFileType(file_type) => {
+                write!(f, "ELF file type {} can't be loaded for execution", file_type)
+            }
+            ElfioError::SegmentAddressOutOfRange {
+                index,
+                address,
+                size,
+            } => write!(
+                f,
+                "Segment {} (address 0x{:x}, size 0x{:x}) is out of address space",
+                index, address, size
+            ),
+            ElfioError::SectionOutOfSegment { section, segment } => {
+                write!(f, "Section {} doesn't fit into segment {}", section, segment)
+            }
+            ElfioError::LayoutOverflow => {
+                write!(f, "File offsets or addresses of the layout overflow")
+            }
+            ElfioError::MissingStringTable { section } => {
+                write!(f, "Section {} isn't linked to a string table", section)
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+impl error::Error for ElfioError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ElfioError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+impl From<io::Error> for ElfioError {
+    fn from(e: io::Error) -> Self {
+        ElfioError::Io(e)
+    }
+}
+
+// --------------------------------------------------------------------------
+impl From<ElfioError> for io::Error {
+    fn from(e: ElfioError) -> Self {
+        match e {
+            ElfioError::Io(e) => e,
+            _ => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
@@ -0,0 +1,804 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+use std::convert::TryFrom;
+
+use super::elfio::*;
+use super::section::*;
+use super::symbols::*;
+use super::types::*;
+use super::utils::{Convert, Converter};
+
+// Bucket counts used by GNU ld for the number of hashed symbols
+const BUCKETS_NUM: [ElfWord; 19] = [
+    1, 3, 17, 37, 67, 97, 131, 197, 263, 521, 1031, 2053, 4099, 8209, 16411, 32771, 65537,
+    131101, 262147,
+];
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to SysV symbol hash tables
+///
+/// For example:
+/// ```
+/// use std::fs::File;
+/// use std::io;
+/// use std::io::{BufReader, Error};
+///
+/// use elfio::{Elfio, HashSectionAccessor};
+///
+/// fn main() -> io::Result<()> {
+///     let elf_file = File::open("tests/files/hello_ppc")?;
+///     let mut reader = BufReader::new(elf_file);
+///
+///     let mut elf = Elfio::new();
+///
+///     elf.load(&mut reader)?;
+///
+///     let section = match elf.get_section_by_name(".hash") {
+///         Some(s) => s,
+///         None => return Err(Error::new(io::ErrorKind::Other, "section not found")),
+///     };
+///
+///     let hash = HashSectionAccessor::new(&elf, section);
+///     assert_eq!(hash.get_buckets_num(), 3);
+///     assert_eq!(hash.get_chains_num(), 13);
+///     assert_eq!(hash.get_bucket(0), Some(12));
+///     assert_eq!(hash.find_symbol("_ZSt4cout").unwrap().0, 9);
+///
+///     Ok(())
+/// }
+/// ```
+pub struct HashSectionAccessor<'a> {
+    elfio:   &'a Elfio<'a>,
+    section: &'a dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> HashSectionAccessor<'a> {
+    /// Creates a new instance of the hash table accessor
+    pub fn new(elfio: &'a Elfio<'a>, section: &'a dyn ElfSectionTrait) -> HashSectionAccessor<'a> {
+        HashSectionAccessor { elfio, section }
+    }
+
+    /// Returns the number of buckets
+    pub fn get_buckets_num(&self) -> ElfWord {
+        self.get_word(0).unwrap_or(0)
+    }
+
+    /// Returns the number of chain entries. It is equal to the number of
+    /// symbols in the linked symbol table
+    pub fn get_chains_num(&self) -> ElfWord {
+        self.get_word(1).unwrap_or(0)
+    }
+
+    /// Returns the index of the first symbol in the bucket
+    pub fn get_bucket(&self, index: ElfWord) -> Option<ElfWord> {
+        if index >= self.get_buckets_num() {
+            return None;
+        }
+
+        self.get_word(2 + index as ElfXword)
+    }
+
+    /// Returns the index of the symbol following the given one in its chain
+    pub fn get_chain(&self, index: ElfWord) -> Option<ElfWord> {
+        if index >= self.get_chains_num() {
+            return None;
+        }
+
+        self.get_word(2 + self.get_buckets_num() as ElfXword + index as ElfXword)
+    }
+
+    /// Finds a symbol by its name in the linked symbol table
+    pub fn find_symbol(&self, name: &str) -> Option<(ElfXword, Symbol)> {
+        let symbols = get_symbol_accessor(self.elfio, self.section)?;
+        let mut found = None;
+        sysv_lookup(
+            self.section.get_data(),
+            self.elfio.get_converter(),
+            name,
+            |index| match_symbol(&symbols, index, name, &mut found),
+        );
+
+        found
+    }
+
+    fn get_word(&self, index: ElfXword) -> Option<ElfWord> {
+        get_word(self.section.get_data(), self.elfio.get_converter(), index)
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to GNU symbol hash tables
+///
+/// For example:
+/// ```
+/// use std::fs::File;
+/// use std::io;
+/// use std::io::{BufReader, Error};
+///
+/// use elfio::{Elfio, GnuHashSectionAccessor};
+///
+/// fn main() -> io::Result<()> {
+///     let elf_file = File::open("tests/files/hello_ppc64")?;
+///     let mut reader = BufReader::new(elf_file);
+///
+///     let mut elf = Elfio::new();
+///
+///     elf.load(&mut reader)?;
+///
+///     let section = match elf.get_section_by_name(".gnu.hash") {
+///         Some(s) => s,
+///         None => return Err(Error::new(io::ErrorKind::Other, "section not found")),
+///     };
+///
+///     let hash = GnuHashSectionAccessor::new(&elf, section);
+///     assert_eq!(hash.get_buckets_num(), 2);
+///     assert_eq!(hash.get_symbol_offset(), 24);
+///     assert_eq!(hash.get_bloom_size(), 1);
+///     assert_eq!(hash.get_bloom_shift(), 6);
+///     assert_eq!(hash.get_bloom_word(0), Some(0x0000800000000080));
+///     assert_eq!(hash.get_bucket(1), Some(24));
+///     assert_eq!(hash.find_symbol("error").unwrap().0, 24);
+///     // Undefined symbols are not hashed
+///     assert!(hash.find_symbol("strlen").is_none());
+///
+///     Ok(())
+/// }
+/// ```
+pub struct GnuHashSectionAccessor<'a> {
+    elfio:   &'a Elfio<'a>,
+    section: &'a dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> GnuHashSectionAccessor<'a> {
+    /// Creates a new instance of the GNU hash table accessor
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> GnuHashSectionAccessor<'a> {
+        GnuHashSectionAccessor { elfio, section }
+    }
+
+    /// Returns the number of buckets
+    pub fn get_buckets_num(&self) -> ElfWord {
+        self.get_word(0).unwrap_or(0)
+    }
+
+    /// Returns the index of the first hashed symbol. Symbols in front of
+    /// it are not accessible by the hash table
+    pub fn get_symbol_offset(&self) -> ElfWord {
+        self.get_word(1).unwrap_or(0)
+    }
+
+    /// Returns the number of the bloom filter words
+    pub fn get_bloom_size(&self) -> ElfWord {
+        self.get_word(2).unwrap_or(0)
+    }
+
+    /// Returns the shift count used by the bloom filter
+    pub fn get_bloom_shift(&self) -> ElfWord {
+        self.get_word(3).unwrap_or(0)
+    }
+
+    /// Returns the bloom filter word. The words are 64 bit long for
+    /// ELFCLASS64 files and 32 bit long otherwise
+    pub fn get_bloom_word(&self, index: ElfWord) -> Option<u64> {
+        if index >= self.get_bloom_size() {
+            return None;
+        }
+
+        get_bloom_word(
+            self.section.get_data(),
+            self.elfio.get_converter(),
+            self.elfio.get_class(),
+            index as ElfXword,
+        )
+    }
+
+    /// Returns the index of the first symbol in the bucket or 0 for an
+    /// empty bucket
+    pub fn get_bucket(&self, index: ElfWord) -> Option<ElfWord> {
+        if index >= self.get_buckets_num() {
+            return None;
+        }
+
+        self.get_word(self.get_buckets_pos() + index as ElfXword)
+    }
+
+    /// Returns the number of chain entries
+    pub fn get_chains_num(&self) -> ElfWord {
+        let words_num = self.section.get_data().len() as ElfXword / 4;
+        let chains_pos = self.get_buckets_pos() + self.get_buckets_num() as ElfXword;
+        words_num.saturating_sub(chains_pos) as ElfWord
+    }
+
+    /// Returns the chain entry for the symbol with the index
+    /// `get_symbol_offset() + index`. It is the symbol name hash with the
+    /// lowest bit set for the last symbol of the chain
+    pub fn get_chain(&self, index: ElfWord) -> Option<ElfWord> {
+        if index >= self.get_chains_num() {
+            return None;
+        }
+
+        let chains_pos = self.get_buckets_pos() + self.get_buckets_num() as ElfXword;
+        self.get_word(chains_pos + index as ElfXword)
+    }
+
+    /// Finds a defined symbol by its name in the linked symbol table
+    pub fn find_symbol(&self, name: &str) -> Option<(ElfXword, Symbol)> {
+        let symbols = get_symbol_accessor(self.elfio, self.section)?;
+        let mut found = None;
+        gnu_lookup(
+            self.section.get_data(),
+            self.elfio.get_converter(),
+            self.elfio.get_class(),
+            name,
+            |index| match_symbol(&symbols, index, name, &mut found),
+        );
+
+        found
+    }
+
+    // Returns the word position of the buckets that follow the bloom filter
+    fn get_buckets_pos(&self) -> ElfXword {
+        let bloom_words = if self.elfio.get_class() == constant::ELFCLASS64 { 2 } else { 1 };
+        4 + self.get_bloom_size() as ElfXword * bloom_words
+    }
+
+    fn get_word(&self, index: ElfXword) -> Option<ElfWord> {
+        get_word(self.section.get_data(), self.elfio.get_converter(), index)
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to generate SysV symbol hash tables
+///
+/// The hash table is built for the symbol table referred by the section
+/// link. It should be regenerated after the symbol table modification.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, HashSectionAccessor, HashSectionAccessorMut, Symbol,
+///             SymbolSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2LSB);
+/// let dynstr = elf.add_section(".dynstr");
+/// dynstr.set_type(constant::SHT_STRTAB);
+/// let dynstr_index = dynstr.get_index();
+/// let dynsym = elf.add_section(".dynsym");
+/// dynsym.set_type(constant::SHT_DYNSYM);
+/// dynsym.set_link(dynstr_index as u32);
+/// let dynsym_index = dynsym.get_index();
+/// let hash = elf.add_section(".hash");
+/// hash.set_type(constant::SHT_HASH);
+/// hash.set_link(dynsym_index as u32);
+/// let hash_index = hash.get_index();
+///
+/// let mut dynsym = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+/// for name in ["foo", "bar", "baz"].iter() {
+///     let symbol = Symbol {
+///         name: name.to_string(),
+///         bind: constant::STB_GLOBAL,
+///         shndx: 1,
+///         ..Default::default()
+///     };
+///     dynsym.add_symbol(&symbol, |_, _| ());
+/// }
+///
+/// let mut hash = HashSectionAccessorMut::new(&mut elf, hash_index).unwrap();
+/// assert!(hash.generate());
+///
+/// let hash = HashSectionAccessor::new(&elf, elf.get_section_by_index(hash_index).unwrap());
+/// assert_eq!(hash.get_buckets_num(), 3);
+/// assert_eq!(hash.get_chains_num(), 4);
+/// assert_eq!(hash.find_symbol("bar").unwrap().0, 2);
+/// ```
+pub struct HashSectionAccessorMut<'a, 'b> {
+    elfio: &'a mut Elfio<'b>,
+    index: ElfWord,
+}
+
+// --------------------------------------------------------------------------
+impl<'a, 'b> HashSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the hash table accessor for the section
+    /// with the given index. Returns `None` if there is no such section or
+    /// the data of the section or of the section it is linked to fails
+    /// to be read from a lazily loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<HashSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+
+        Some(HashSectionAccessorMut { elfio, index })
+    }
+
+    /// Regenerates the hash table for all symbols of the linked symbol
+    /// table. Returns `false` if there is no linked symbol table
+    pub fn generate(&mut self) -> bool {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        let names = match get_symbol_names(self.elfio, section) {
+            Some(names) => names,
+            None => return false,
+        };
+
+        let nbucket = get_buckets_num(names.len());
+        let mut buckets = vec![0; nbucket as usize];
+        let mut chains = vec![0; names.len()];
+        // The null symbol is not hashed
+        for (index, name) in names.iter().enumerate().skip(1) {
+            let bucket = (elf_hash(name.as_bytes()) % nbucket) as usize;
+            chains[index] = buckets[bucket];
+            buckets[bucket] = index as ElfWord;
+        }
+
+        let converter = *self.elfio.get_converter();
+        let mut data = Vec::new();
+        push_words(&mut data, &converter, &[nbucket, names.len() as ElfWord]);
+        push_words(&mut data, &converter, &buckets);
+        push_words(&mut data, &converter, &chains);
+
+        let section = self.elfio.get_section_by_index_mut(self.index).unwrap();
+        section.set_data(&data);
+        section.set_entry_size(4);
+
+        true
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to generate GNU symbol hash tables
+///
+/// GNU hash tables require the hashed symbols to be placed at the end of
+/// the symbol table and grouped by their buckets. Local and undefined
+/// symbols are not hashed. So, the linked symbol table is rearranged by
+/// the generator.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, GnuHashSectionAccessor, GnuHashSectionAccessorMut, Symbol,
+///             SymbolSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let dynstr = elf.add_section(".dynstr");
+/// dynstr.set_type(constant::SHT_STRTAB);
+/// let dynstr_index = dynstr.get_index();
+/// let dynsym = elf.add_section(".dynsym");
+/// dynsym.set_type(constant::SHT_DYNSYM);
+/// dynsym.set_link(dynstr_index as u32);
+/// let dynsym_index = dynsym.get_index();
+/// let hash = elf.add_section(".gnu.hash");
+/// hash.set_type(constant::SHT_GNU_HASH);
+/// hash.set_link(dynsym_index as u32);
+/// let hash_index = hash.get_index();
+///
+/// let mut dynsym = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+/// for (name, shndx) in [("foo", 1), ("puts", constant::SHN_UNDEF as u32)].iter() {
+///     let symbol = Symbol {
+///         name: name.to_string(),
+///         bind: constant::STB_GLOBAL,
+///         shndx: *shndx,
+///         ..Default::default()
+///     };
+///     dynsym.add_symbol(&symbol, |_, _| ());
+/// }
+///
+/// let mut moved = Vec::new();
+/// let mut hash = GnuHashSectionAccessorMut::new(&mut elf, hash_index).unwrap();
+/// assert!(hash.generate(|old, new| moved.push((old, new))));
+/// // The undefined symbol is moved in front of the hashed one
+/// assert_eq!(moved, [(2, 1), (1, 2)]);
+///
+/// let hash = GnuHashSectionAccessor::new(&elf, elf.get_section_by_index(hash_index).unwrap());
+/// assert_eq!(hash.get_symbol_offset(), 2);
+/// assert_eq!(hash.find_symbol("foo").unwrap().0, 2);
+/// ```
+pub struct GnuHashSectionAccessorMut<'a, 'b> {
+    elfio: &'a mut Elfio<'b>,
+    index: ElfWord,
+}
+
+// --------------------------------------------------------------------------
+impl<'a, 'b> GnuHashSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the GNU hash table accessor for the
+    /// section with the given index. Returns `None` if there is no such
+    /// section or the data of the section or of the section it is linked
+    /// to fails to be read from a lazily loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<GnuHashSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+
+        Some(GnuHashSectionAccessorMut { elfio, index })
+    }
+
+    /// Rearranges the linked symbol table and regenerates the hash table
+    /// for its defined non-local symbols. The symbol version and extended
+    /// section index tables of the symbol table are rearranged the same way,
+    /// SysV hash tables of the symbol table are regenerated. `func` is called
+    /// with the old and the new indices of every moved symbol, so that other
+    /// references to them, for example, by relocations can be updated.
+    /// Returns `false` if there is no linked symbol table
+    pub fn generate<F>(&mut self, mut func: F) -> bool
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        let symtab_index = section.get_link();
+        let names = match get_symbol_names(self.elfio, section) {
+            Some(names) => names,
+            None => return false,
+        };
+        let symtab = self.elfio.get_section_by_index(symtab_index).unwrap();
+        let symbols = SymbolSectionAccessor::new(self.elfio, symtab);
+        let first_global = symtab.get_info() as usize;
+        let is_hashed: Vec<bool> = (0..names.len())
+            .map(|index| {
+                index != 0
+                    && index >= first_global
+                    && symbols
+                        .get_symbol(index as ElfXword)
+                        .is_some_and(|symbol| symbol.shndx != constant::SHN_UNDEF as ElfWord)
+            })
+            .collect();
+
+        let (unhashed, mut hashed): (Vec<usize>, Vec<usize>) =
+            (0..names.len()).partition(|&index| !is_hashed[index]);
+        let nbuckets = get_buckets_num(hashed.len());
+        let hashes: Vec<ElfWord> = names.iter().map(|name| gnu_hash(name.as_bytes())).collect();
+        hashed.sort_by_key(|&index| hashes[index] % nbuckets);
+
+        // Rearrange the symbol table
+        let entry_size = symtab.get_entry_size() as usize;
+        let old_data = symtab.get_data();
+        let order: Vec<usize> = unhashed.iter().chain(hashed.iter()).copied().collect();
+        let mut data = Vec::with_capacity(old_data.len());
+        for &index in &order {
+            data.extend_from_slice(&old_data[index * entry_size..(index + 1) * entry_size]);
+        }
+        for (new, &old) in order.iter().enumerate() {
+            if new != old {
+                func(old as ElfXword, new as ElfXword);
+            }
+        }
+        self.elfio
+            .get_section_by_index_mut(symtab_index)
+            .unwrap()
+            .set_data(&data);
+        self.update_linked_sections(symtab_index, &order);
+
+        // Bloom filter parameters are chosen the way GNU ld does
+        let is_64 = self.elfio.get_class() == constant::ELFCLASS64;
+        let word_bits_log2 = if is_64 { 6 } else { 5 };
+        let mut mask_bits_log2 = ceil_log2(hashed.len()) + 1;
+        if mask_bits_log2 < 3 {
+            mask_bits_log2 = 5;
+        } else if (1 << (mask_bits_log2 - 2)) & hashed.len() != 0 {
+            mask_bits_log2 += 3;
+        } else {
+            mask_bits_log2 += 2;
+        }
+        mask_bits_log2 = mask_bits_log2.max(word_bits_log2);
+        let bloom_shift = mask_bits_log2 as ElfWord;
+        let bloom_size = 1 << (mask_bits_log2 - word_bits_log2);
+        let word_bits = 1 << word_bits_log2;
+
+        let symoffset = unhashed.len();
+        let mut bloom = vec![0u64; bloom_size];
+        let mut buckets = vec![0; nbuckets as usize];
+        let mut chains = vec![0; hashed.len()];
+        for (pos, &index) in hashed.iter().enumerate() {
+            let hash = hashes[index];
+            let bucket = (hash % nbuckets) as usize;
+            if buckets[bucket] == 0 {
+                buckets[bucket] = (symoffset + pos) as ElfWord;
+            }
+            // The lowest bit marks the last symbol in the bucket
+            let is_last = hashed
+                .get(pos + 1)
+                .is_none_or(|&next| (hashes[next] % nbuckets) as usize != bucket);
+            chains[pos] = if is_last { hash | 1 } else { hash & !1 };
+
+            bloom[(hash / word_bits) as usize % bloom_size] |=
+                1 << (hash % word_bits) | 1 << ((hash >> bloom_shift) % word_bits);
+        }
+
+        let converter = *self.elfio.get_converter();
+        let header = [nbuckets, symoffset as ElfWord, bloom_size as ElfWord, bloom_shift];
+        let mut data = Vec::new();
+        push_words(&mut data, &converter, &header);
+        for &word in &bloom {
+            if is_64 {
+                data.extend_from_slice(&converter.convert(word).to_ne_bytes());
+            } else {
+                data.extend_from_slice(&converter.convert(word as u32).to_ne_bytes());
+            }
+        }
+        push_words(&mut data, &converter, &buckets);
+        push_words(&mut data, &converter, &chains);
+
+        let section = self.elfio.get_section_by_index_mut(self.index).unwrap();
+        section.set_data(&data);
+
+        true
+    }
+
+    // Keeps the sections referring to the symbols by their indices
+    // consistent with the rearranged symbol table
+    fn update_linked_sections(&mut self, symtab_index: ElfWord, order: &[usize]) {
+        let linked: Vec<(ElfWord, ElfWord)> = self
+            .elfio
+            .get_sections()
+            .iter()
+            .filter(|section| section.get_link() == symtab_index)
+            .map(|section| (section.get_index(), section.get_type()))
+            .collect();
+
+        for (index, section_type) in linked {
+            match section_type {
+                constant::SHT_GNU_VERSYM | constant::SHT_SYMTAB_SHNDX => {
+                    let section = self.elfio.get_section_by_index_mut(index).unwrap();
+                    if let Some(data) = reorder_entries(section.get_data(), order) {
+                        section.set_data(&data);
+                    }
+                }
+                constant::SHT_HASH => {
+                    HashSectionAccessorMut::new(self.elfio, index).unwrap().generate();
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+// Rearranges entries of a table parallel to the symbol table. `None` is
+// returned if the table size doesn't match the number of symbols
+fn reorder_entries(data: &[u8], order: &[usize]) -> Option<Vec<u8>> {
+    let entry_size = data.len().checked_div(order.len())?;
+    if entry_size * order.len() != data.len() {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len());
+    for &index in order {
+        result.extend_from_slice(&data[index * entry_size..(index + 1) * entry_size]);
+    }
+
+    Some(result)
+}
+
+// --------------------------------------------------------------------------
+// Returns the accessor of the symbol table linked to the hash table
+fn get_symbol_accessor<'a>(
+    elfio: &'a Elfio<'a>,
+    hash: &dyn ElfSectionTrait,
+) -> Option<SymbolSectionAccessor<'a>> {
+    let symtab = elfio.get_section_by_index(hash.get_link())?;
+    Some(SymbolSectionAccessor::new(elfio, symtab))
+}
+
+// --------------------------------------------------------------------------
+// Returns names of all symbols of the symbol table linked to the hash table.
+// `None` is returned if there is no linked symbol table
+fn get_symbol_names(elfio: &Elfio, hash: &dyn ElfSectionTrait) -> Option<Vec<String>> {
+    let link = hash.get_link();
+    if link == constant::SHN_UNDEF as ElfWord || link == hash.get_index() {
+        return None;
+    }
+
+    let symtab = elfio.get_section_by_index(link)?;
+    if symtab.get_entry_size() == 0 {
+        return None;
+    }
+
+    let symbols = SymbolSectionAccessor::new(elfio, symtab);
+    (0..symbols.get_symbols_num())
+        .map(|index| symbols.get_symbol(index).map(|symbol| symbol.name))
+        .collect()
+}
+
+// --------------------------------------------------------------------------
+// Remembers the symbol with the given index if it has the name
+fn match_symbol(
+    symbols: &SymbolSectionAccessor,
+    index: ElfXword,
+    name: &str,
+    found: &mut Option<(ElfXword, Symbol)>,
+) -> bool {
+    match symbols.get_symbol(index) {
+        Some(symbol) if symbol.name == name => {
+            *found = Some((index, symbol));
+            true
+        }
+        _ => false,
+    }
+}
+
+// --------------------------------------------------------------------------
+// Returns the number of buckets for the number of hashed symbols
+fn get_buckets_num(symbols_num: usize) -> ElfWord {
+    BUCKETS_NUM
+        .iter()
+        .rev()
+        .find(|&&buckets_num| buckets_num as usize <= symbols_num)
+        .copied()
+        .unwrap_or(1)
+}
+
+// --------------------------------------------------------------------------
+fn ceil_log2(value: usize) -> usize {
+    if value <= 1 {
+        0
+    } else {
+        (usize::BITS - (value - 1).leading_zeros()) as usize
+    }
+}
+
+// --------------------------------------------------------------------------
+// Appends the words to the section data
+fn push_words(data: &mut Vec<u8>, converter: &Converter, words: &[ElfWord]) {
+    for &word in words {
+        data.extend_from_slice(&converter.convert(word).to_ne_bytes());
+    }
+}
+
+// --------------------------------------------------------------------------
+/// The hash function of SysV hash tables
+pub fn elf_hash(name: &[u8]) -> ElfWord {
+    let mut hash: ElfWord = 0;
+    for &c in name {
+        hash = (hash << 4).wrapping_add(c as ElfWord);
+        let high = hash & 0xF000_0000;
+        hash ^= high >> 24;
+        hash &= !high;
+    }
+
+    hash
+}
+
+// --------------------------------------------------------------------------
+/// The hash function of GNU hash tables
+pub fn gnu_hash(name: &[u8]) -> ElfWord {
+    name.iter().fold(5381, |hash: ElfWord, &c| {
+        hash.wrapping_mul(33).wrapping_add(c as ElfWord)
+    })
+}
+
+// --------------------------------------------------------------------------
+// Reads a hash table word by its index
+fn get_word(data: &[u8], converter: &Converter, index: ElfXword) -> Option<ElfWord> {
+    let offset = usize::try_from(index.checked_mul(4)?).ok()?;
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+
+    Some(converter.convert(u32::from_ne_bytes(<[u8; 4]>::try_from(bytes).ok()?)))
+}
+
+// --------------------------------------------------------------------------
+// Reads a GNU hash table bloom filter word by its index. The bloom filter
+// follows the four word header; its words are 64 bit long for ELFCLASS64
+fn get_bloom_word(
+    data: &[u8],
+    converter: &Converter,
+    class: u8,
+    index: ElfXword,
+) -> Option<u64> {
+    if class == constant::ELFCLASS64 {
+        let offset = usize::try_from(index.checked_mul(8)?.checked_add(16)?).ok()?;
+        let bytes = data.get(offset..offset.checked_add(8)?)?;
+        Some(converter.convert(u64::from_ne_bytes(<[u8; 8]>::try_from(bytes).ok()?)))
+    } else {
+        get_word(data, converter, index.checked_add(4)?).map(|word| word as u64)
+    }
+}
+
+// --------------------------------------------------------------------------
+// Walks the SysV hash chain of the name and returns the first symbol index
+// accepted by `is_match`
+fn sysv_lookup<F>(
+    data: &[u8],
+    converter: &Converter,
+    name: &str,
+    mut is_match: F,
+) -> Option<ElfXword>
+where
+    F: FnMut(ElfXword) -> bool,
+{
+    let nbucket = get_word(data, converter, 0)? as ElfXword;
+    let nchain = get_word(data, converter, 1)? as ElfXword;
+    if nbucket == 0 {
+        return None;
+    }
+
+    let hash = elf_hash(name.as_bytes()) as ElfXword;
+    let mut index = get_word(data, converter, 2 + hash % nbucket)? as ElfXword;
+    // A chain can't be longer than the number of symbols. The limit breaks
+    // loops in malformed tables
+    for _ in 0..nchain {
+        if index == 0 || index >= nchain {
+            break;
+        }
+        if is_match(index) {
+            return Some(index);
+        }
+        index = get_word(data, converter, 2 + nbucket + index)? as ElfXword;
+    }
+
+    None
+}
+
+// --------------------------------------------------------------------------
+// Checks the GNU hash bloom filter and walks the hash chain of the name.
+// Returns the first symbol index accepted by `is_match`
+fn gnu_lookup<F>(
+    data: &[u8],
+    converter: &Converter,
+    class: u8,
+    name: &str,
+    mut is_match: F,
+) -> Option<ElfXword>
+where
+    F: FnMut(ElfXword) -> bool,
+{
+    let nbuckets = get_word(data, converter, 0)? as ElfXword;
+    let symoffset = get_word(data, converter, 1)? as ElfXword;
+    let bloom_size = get_word(data, converter, 2)? as ElfXword;
+    let bloom_shift = get_word(data, converter, 3)?;
+    if nbuckets == 0 || bloom_size == 0 {
+        return None;
+    }
+
+    let hash = gnu_hash(name.as_bytes());
+    let bloom_words = if class == constant::ELFCLASS64 { 2 } else { 1 };
+    let bits = 32 * bloom_words as ElfWord;
+    let bloom_index = (hash / bits) as ElfXword % bloom_size;
+    let bloom = get_bloom_word(data, converter, class, bloom_index)?;
+    let second_bit = hash.checked_shr(bloom_shift).unwrap_or(0) % bits;
+    let mask = 1u64 << (hash % bits) | 1u64 << second_bit;
+    if bloom & mask != mask {
+        return None;
+    }
+
+    let buckets = 4 + bloom_size * bloom_words;
+    let chains = buckets + nbuckets;
+    let mut index = get_word(data, converter, buckets + hash as ElfXword % nbuckets)? as ElfXword;
+    if index < symoffset {
+        return None;
+    }
+
+    loop {
+        let chain_hash = get_word(data, converter, chains + index - symoffset)?;
+        if hash | 1 == chain_hash | 1 && is_match(index) {
+            return Some(index);
+        }
+        // The lowest bit marks the end of the chain
+        if chain_hash & 1 != 0 {
+            return None;
+        }
+        index += 1;
+    }
+}
@@ -49,12 +49,10 @@ pub trait ElfHeaderAccessTrait {
     ELFIO_GET_SET_ACCESS_DECL!(ElfHalf, segments_num);
     ELFIO_GET_SET_ACCESS_DECL!(Elf64Off, segments_offset);
     ELFIO_GET_SET_ACCESS_DECL!(ElfHalf, section_name_str_index);
-
-    fn set_converter(&mut self, converter: &Converter);
 }
 
 // --------------------------------------------------------------------------
-pub trait ElfHeaderTrait: ElfHeaderAccessTrait + Load {}
+pub trait ElfHeaderTrait: ElfHeaderAccessTrait + Load + Save {}
 
 // --------------------------------------------------------------------------
 // ELF file header
@@ -86,18 +84,30 @@ where
     Offset: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset>,
 {
-    pub fn new() -> ElfHeader<Addr, Offset> {
+    pub fn new(conv: &Converter, encoding: u8) -> ElfHeader<Addr, Offset> {
+        let class = if std::mem::size_of::<Addr>() == 8 {
+            constant::ELFCLASS64
+        } else {
+            constant::ELFCLASS32
+        };
+        let (header_size, segment_entry_size, section_entry_size) = if class == constant::ELFCLASS64
+        {
+            (64, 56, 64)
+        } else {
+            (52, 32, 40)
+        };
+
         ElfHeader::<Addr, Offset> {
-            converter: Converter { is_needed: false },
+            converter: *conv,
 
             e_ident:     [
                 constant::ELFMAG0,
                 constant::ELFMAG1,
                 constant::ELFMAG2,
                 constant::ELFMAG3,
-                constant::ELFCLASSNONE,
-                constant::ELFDATANONE,
-                constant::EV_NONE,
+                class,
+                encoding,
+                constant::EV_CURRENT,
                 0,
                 0,
                 0,
@@ -110,15 +120,15 @@ where
             ],
             e_type:      0,
             e_machine:   0,
-            e_version:   0,
+            e_version:   conv.convert(constant::EV_CURRENT as ElfWord),
             e_entry:     Addr::zero(),
             e_phoff:     Offset::zero(),
             e_shoff:     Offset::zero(),
             e_flags:     0,
-            e_ehsize:    0,
-            e_phentsize: 0,
+            e_ehsize:    conv.convert(header_size as ElfHalf),
+            e_phentsize: conv.convert(segment_entry_size as ElfHalf),
             e_phnum:     0,
-            e_shentsize: 0,
+            e_shentsize: conv.convert(section_entry_size as ElfHalf),
             e_shnum:     0,
             e_shstrndx:  0,
         }
@@ -130,8 +140,8 @@ impl<Addr, Offset> ElfHeaderTrait for ElfHeader<Addr, Offset>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset>,
-    Addr: Zero + Load + AsPrimitive<u64>,
-    Offset: Zero + Load + AsPrimitive<u64>,
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset>,
 {
 }
@@ -164,10 +174,6 @@ where
     ELFIO_GET_SET_ACCESS!(Elf64Off, sections_offset, e_shoff);
     ELFIO_GET_SET_ACCESS!(ElfHalf, segments_num, e_phnum);
     ELFIO_GET_SET_ACCESS!(Elf64Off, segments_offset, e_phoff);
-
-    fn set_converter(&mut self, converter: &Converter) {
-        self.converter = *converter;
-    }
 }
 
 // --------------------------------------------------------------------------
@@ -177,7 +183,7 @@ where
     Offset: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset>,
 {
-    fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
         (&mut self.e_ident).load(reader)?;
         self.e_type.load(reader)?;
         self.e_machine.load(reader)?;
@@ -196,3 +202,30 @@ where
         Ok(())
     }
 }
+
+// --------------------------------------------------------------------------
+impl<Addr, Offset> Save for ElfHeader<Addr, Offset>
+where
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
+    Converter: Convert<Addr> + Convert<Offset>,
+{
+    fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        self.e_ident.save(writer)?;
+        self.e_type.save(writer)?;
+        self.e_machine.save(writer)?;
+        self.e_version.save(writer)?;
+        self.e_entry.save(writer)?;
+        self.e_phoff.save(writer)?;
+        self.e_shoff.save(writer)?;
+        self.e_flags.save(writer)?;
+        self.e_ehsize.save(writer)?;
+        self.e_phentsize.save(writer)?;
+        self.e_phnum.save(writer)?;
+        self.e_shentsize.save(writer)?;
+        self.e_shnum.save(writer)?;
+        self.e_shstrndx.save(writer)?;
+
+        Ok(())
+    }
+}
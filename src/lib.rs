@@ -36,10 +36,14 @@
 #[macro_use]
 mod macros;
 
+mod address;
 mod array;
 mod dynamic;
 mod elfio;
+mod error;
+mod hash;
 mod header;
+mod loader;
 mod modinfo;
 mod note;
 mod relocation;
@@ -49,10 +53,15 @@ mod strings;
 mod symbols;
 mod types;
 mod utils;
+mod versym;
 
 pub use crate::elfio::*;
+pub use address::*;
 pub use array::*;
 pub use dynamic::*;
+pub use error::*;
+pub use hash::*;
+pub use loader::*;
 pub use modinfo::*;
 pub use note::*;
 pub use relocation::*;
@@ -62,3 +71,5 @@ pub use strings::*;
 pub use symbols::*;
 pub use types::*;
 pub use utils::ElfioReadSeek;
+pub use utils::ElfioWriteSeek;
+pub use versym::*;
@@ -0,0 +1,225 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+use std::io;
+
+use super::address::*;
+use super::elfio::*;
+use super::error::*;
+use super::types::*;
+
+// --------------------------------------------------------------------------
+/// Access permissions of a loaded memory range
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    /// The memory can be read
+    pub read:    bool,
+    /// The memory can be written
+    pub write:   bool,
+    /// The memory can be executed
+    pub execute: bool,
+}
+
+impl Permissions {
+    /// Creates the permissions from the segment flags
+    pub fn from_flags(flags: ElfWord) -> Permissions {
+        Permissions {
+            read:    flags & constant::PF_R != 0,
+            write:   flags & constant::PF_W != 0,
+            execute: flags & constant::PF_X != 0,
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// The program state after its image is placed in memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedImage {
+    /// Entry point of the program
+    pub entry:         Elf64Addr,
+    /// Initial program break: the end of the highest loaded segment.
+    /// It is not rounded up to the page size
+    pub program_break: Elf64Addr,
+    /// Auxiliary vector entries describing the program:
+    /// AT_PHDR, AT_PHENT, AT_PHNUM and AT_ENTRY
+    pub auxv:          Vec<(ElfXword, ElfXword)>,
+}
+
+// --------------------------------------------------------------------------
+/// Places the program image into the memory of an emulator. The `writer`
+/// is called for every PT_LOAD segment with its virtual address, file image,
+/// the number of zero bytes following the file image, like `.bss`, and
+/// the segment permissions. `writer` must write the file image at the address
+/// and zero the given number of bytes following it, as the memory may hold
+/// anything before. The zero bytes are passed as a count, so a huge memory
+/// size taken from the file doesn't make the loader allocate it.
+/// `zero_filling_writer` makes such a writer from a function writing bytes
+/// only. Errors returned by `writer` stop the loading
+///
+/// For example:
+/// ```
+/// use elfio::{load_image, constant, Elfio};
+///
+/// let data = std::fs::read("tests/files/hello_ppc").unwrap();
+/// let elf = Elfio::from_bytes(&data).unwrap();
+///
+/// let mut ranges = Vec::new();
+/// let image = load_image(&elf, |address, data, zeros, permissions| {
+///     ranges.push((address, data.len(), zeros, permissions.write));
+///     Ok(())
+/// })
+/// .unwrap();
+///
+/// assert_eq!(ranges, [(0x10000000, 0xacc, 0, false), (0x10010acc, 0x140, 0x98, true)]);
+/// assert_eq!(image.entry, 0x10000550);
+/// assert_eq!(image.program_break, 0x10010ca4);
+/// assert!(image.auxv.contains(&(constant::AT_PHDR, 0x10000034)));
+/// ```
+pub fn load_image<F>(elfio: &Elfio, mut writer: F) -> Result<LoadedImage, ElfioError>
+where
+    F: FnMut(Elf64Addr, &[u8], ElfXword, Permissions) -> io::Result<()>,
+{
+    let file_type = elfio.get_type();
+    if file_type != constant::ET_EXEC && file_type != constant::ET_DYN {
+        return Err(ElfioError::UnsupportedFileType(file_type));
+    }
+
+    let address_limit: Elf64Addr = if elfio.get_class() == constant::ELFCLASS32 {
+        1 << 32
+    } else {
+        Elf64Addr::MAX
+    };
+
+    let memory = AddressSpace::new(elfio);
+    let mut program_break = 0;
+    for (index, segment) in elfio.get_segments().iter().enumerate() {
+        if segment.get_type() != constant::PT_LOAD {
+            continue;
+        }
+
+        let address = segment.get_virtual_address();
+        let size = std::cmp::max(segment.get_memory_size(), segment.get_file_size());
+        let out_of_range = ElfioError::SegmentAddressOutOfRange {
+            index: index as ElfWord,
+            address,
+            size,
+        };
+        let end = match address.checked_add(size) {
+            Some(end) if end <= address_limit => end,
+            _ => return Err(out_of_range),
+        };
+        // Only the file image is read, it is limited by the file size
+        let file_size = segment.get_file_size();
+        let data = memory.read(address, file_size).ok_or(out_of_range)?;
+
+        let permissions = Permissions::from_flags(segment.get_flags());
+        writer(address, &data, size - file_size, permissions)?;
+        program_break = std::cmp::max(program_break, end);
+    }
+
+    let entry = elfio.get_entry();
+    let auxv = vec![
+        (constant::AT_PHDR, get_program_headers_address(elfio, &memory)),
+        (constant::AT_PHENT, elfio.get_segment_entry_size() as ElfXword),
+        (constant::AT_PHNUM, elfio.get_segments().len() as ElfXword),
+        (constant::AT_ENTRY, entry),
+    ];
+
+    Ok(LoadedImage {
+        entry,
+        program_break,
+        auxv,
+    })
+}
+
+// --------------------------------------------------------------------------
+/// Makes a `load_image` writer from the function writing bytes at
+/// an address. The file image of every segment is written first, then
+/// the zero bytes following it are written in chunks of a page at most,
+/// so the memory is zeroed without allocating the whole memory size
+///
+/// For example:
+/// ```
+/// use elfio::{load_image, zero_filling_writer, Elfio};
+///
+/// let data = std::fs::read("tests/files/hello_ppc").unwrap();
+/// let elf = Elfio::from_bytes(&data).unwrap();
+///
+/// let base = 0x10000000;
+/// let mut memory = vec![0xFF; 0x20000];
+/// let writer = zero_filling_writer(|address, bytes, _| {
+///     let start = (address - base) as usize;
+///     memory[start..start + bytes.len()].copy_from_slice(bytes);
+///     Ok(())
+/// });
+/// load_image(&elf, writer).unwrap();
+///
+/// // .bss follows the file image of the second segment
+/// assert_eq!(memory[0x10c0c..0x10ca4], [0; 0x98][..]);
+/// assert_eq!(memory[0x10ca4], 0xFF);
+/// ```
+pub fn zero_filling_writer<W>(
+    mut write: W,
+) -> impl FnMut(Elf64Addr, &[u8], ElfXword, Permissions) -> io::Result<()>
+where
+    W: FnMut(Elf64Addr, &[u8], Permissions) -> io::Result<()>,
+{
+    const ZEROS: [u8; 0x1000] = [0; 0x1000];
+
+    move |address, data, zeros, permissions| {
+        write(address, data, permissions)?;
+        // The address range is checked by the loader, it doesn't overflow
+        let mut address = address + data.len() as Elf64Addr;
+        let mut left = zeros;
+        while left != 0 {
+            let size = std::cmp::min(left, ZEROS.len() as ElfXword);
+            write(address, &ZEROS[..size as usize], permissions)?;
+            address += size;
+            left -= size;
+        }
+
+        Ok(())
+    }
+}
+
+// --------------------------------------------------------------------------
+// The program headers address is given by PT_PHDR. Without it, the address
+// is calculated relative to the first loaded segment like the kernel does
+fn get_program_headers_address(elfio: &Elfio, memory: &AddressSpace) -> Elf64Addr {
+    let segments = elfio.get_segments();
+    if let Some(segment) = segments.iter().find(|s| s.get_type() == constant::PT_PHDR) {
+        return segment.get_virtual_address();
+    }
+
+    let offset = elfio.get_segments_offset();
+    memory.get_address(offset).unwrap_or_else(|| {
+        segments
+            .iter()
+            .find(|segment| segment.get_type() == constant::PT_LOAD)
+            .map_or(0, |segment| {
+                segment
+                    .get_virtual_address()
+                    .wrapping_sub(segment.get_offset())
+                    .wrapping_add(offset)
+            })
+    })
+}
@@ -48,11 +48,11 @@ macro_rules! ELFIO_GET_SET_ACCESS_DECL {
 }
 
 macro_rules! ELFIO_GET_ACCESS {
-    ($type: ident, $name: ident, $field: expr) => {
+    ($type: ident, $name: ident, $($field: tt)+) => {
         paste::paste! {
             fn [<get_ $name>](&self) -> $type {
                 self.converter.convert(
-                    paste::paste! [self. $field]
+                    self.$($field)+
                 ).as_()
             }
         }
@@ -60,15 +60,15 @@ macro_rules! ELFIO_GET_ACCESS {
 }
 
 macro_rules! ELFIO_GET_SET_ACCESS {
-    ($type: ident, $name: ident, $field: expr) => {
+    ($type: ident, $name: ident, $($field: tt)+) => {
         paste::paste! {
             fn [<get_ $name>](&self) -> $type {
                 self.converter.convert(
-                    paste::paste! [self. $field]
+                    self.$($field)+
                 ).as_()
             }
             fn [<set_ $name>](&mut self, value: $type) {
-                paste::paste! [self. $field] = (value).as_();
+                self.$($field)+ = self.converter.convert((value).as_());
             }
         }
     };
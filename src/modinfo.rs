@@ -20,8 +20,7 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 THE SOFTWARE.
 */
 
-use std::collections::hash_map::Iter;
-use std::collections::HashMap;
+use std::slice;
 
 use super::elfio::*;
 use super::section::*;
@@ -56,71 +55,292 @@ use super::types::*;
 ///
 ///     assert_eq!(modinfo.get_entries_num(), 10);
 ///     assert_eq!(modinfo.get(&"description".to_string()).unwrap(), "Platform-independent bitbanging I2C driver");
+///     assert_eq!(modinfo.get_license(), Some("GPL"));
+///     assert_eq!(modinfo.get_depends(), ["i2c-algo-bit"]);
 ///
 ///     Ok(())
 /// }
 /// ```
 pub struct ModInfoSectionAccessor<'a> {
-    _elfio:   &'a Elfio,
+    _elfio:   &'a Elfio<'a>,
     _section: &'a dyn ElfSectionTrait,
-    content:  HashMap<String, String>,
+    content:  Vec<(String, String)>,
 }
 
 // --------------------------------------------------------------------------
 impl<'a> ModInfoSectionAccessor<'a> {
     /// Creates a new instance of the relocation table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> ModInfoSectionAccessor<'a> {
-        let mut mi = ModInfoSectionAccessor {
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> ModInfoSectionAccessor<'a> {
+        ModInfoSectionAccessor {
             _elfio:   elfio,
             _section: section,
-            content:  HashMap::new(),
-        };
-
-        let data = section.get_data();
-        let datalen = data.len();
-
-        if datalen > 0 {
-            let mut i: usize = 0;
-            while i < datalen {
-                while i < datalen && data[i] != 0 {
-                    let mut str = Self::str_from_u8_nul_utf8_unchecked(&data[i..]).to_string();
-                    i += str.len() + 1;
-                    let pos = str.find('=').unwrap();
-                    let value = str.split_off(pos + 1);
-                    str.truncate(pos);
-                    mi.content.insert(str, value);
+            content:  parse_entries(section.get_data()),
+        }
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the number of modinfo entries, including duplicate keys
+    pub fn get_entries_num(&self) -> ElfXword {
+        self.content.len() as ElfXword
+    }
+
+    // --------------------------------------------------------------------------
+    /// Get iterator over all modinfo entries in the section order
+    pub fn get_iter(&self) -> slice::Iter<'_, (String, String)> {
+        self.content.iter()
+    }
+
+    // --------------------------------------------------------------------------
+    /// Retrieve the first value with the given key
+    pub fn get(&self, field: &str) -> Option<&String> {
+        self.content
+            .iter()
+            .find(|(key, _)| key == field)
+            .map(|(_, value)| value)
+    }
+
+    // --------------------------------------------------------------------------
+    /// Retrieve all values with the given key in the section order
+    pub fn get_all(&self, field: &str) -> Vec<&str> {
+        self.content
+            .iter()
+            .filter(|(key, _)| key == field)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the module license
+    pub fn get_license(&self) -> Option<&str> {
+        self.get("license").map(String::as_str)
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the kernel version magic string the module was built for
+    pub fn get_vermagic(&self) -> Option<&str> {
+        self.get("vermagic").map(String::as_str)
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the names of the modules this module depends on. All
+    /// `depends` entries are split on commas; empty names are skipped
+    pub fn get_depends(&self) -> Vec<&str> {
+        self.get_all("depends")
+            .into_iter()
+            .flat_map(|depends| depends.split(','))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the module parameters. `parm` and `parmtype` entries are
+    /// "name:text" strings grouped by the parameter name in the order
+    /// the parameters first appear
+    pub fn get_parameters(&self) -> Vec<ModInfoParameter> {
+        let mut parameters: Vec<ModInfoParameter> = Vec::new();
+
+        for (key, value) in &self.content {
+            if key != "parm" && key != "parmtype" {
+                continue;
+            }
+
+            let (name, text) = match value.find(':') {
+                Some(pos) => (&value[..pos], &value[pos + 1..]),
+                None => (value.as_str(), ""),
+            };
+
+            let pos = match parameters.iter().position(|p| p.name == name) {
+                Some(pos) => pos,
+                None => {
+                    parameters.push(ModInfoParameter {
+                        name: name.to_string(),
+                        ..Default::default()
+                    });
+                    parameters.len() - 1
                 }
+            };
+
+            if key == "parm" {
+                parameters[pos].description = Some(text.to_string());
+            } else {
+                parameters[pos].ptype = Some(text.to_string());
             }
         }
 
-        mi
+        parameters
     }
+}
+
+/// A kernel module parameter
+///
+/// See documentation for [ModInfoSectionAccessor::get_parameters]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ModInfoParameter {
+    /// The parameter name
+    pub name:        String,
+    /// The parameter description taken from the `parm` entry
+    pub description: Option<String>,
+    /// The parameter type taken from the `parmtype` entry
+    pub ptype:       Option<String>,
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to modify modinfo tables. Entries keep
+/// their original order and duplicate keys, such as several `alias` entries,
+/// are preserved. Every modification is written back to the section data
+///
+/// For example:
+/// ```
+/// use elfio::{Elfio, ModInfoSectionAccessorMut};
+///
+/// let mut elf = Elfio::new();
+/// let modinfo = elf.add_section(".modinfo");
+/// modinfo.set_data(b"license=GPL\0alias=pci:v1\0vermagic=5.4.0\0");
+/// let modinfo_index = modinfo.get_index();
+///
+/// let mut modinfo = ModInfoSectionAccessorMut::new(&mut elf, modinfo_index).unwrap();
+/// modinfo.add("alias", "pci:v2");
+/// modinfo.set("vermagic", "5.10.0");
+/// assert_eq!(modinfo.get_all("alias"), ["pci:v1", "pci:v2"]);
+/// assert_eq!(modinfo.remove("license"), 1);
+///
+/// let section = elf.get_section_by_index(modinfo_index).unwrap();
+/// assert_eq!(
+///     section.get_data(),
+///     b"alias=pci:v1\0vermagic=5.10.0\0alias=pci:v2\0"
+/// );
+/// ```
+pub struct ModInfoSectionAccessorMut<'a, 'b> {
+    elfio:   &'a mut Elfio<'b>,
+    index:   ElfWord,
+    content: Vec<(String, String)>,
+}
 
+// --------------------------------------------------------------------------
+impl<'a, 'b> ModInfoSectionAccessorMut<'a, 'b> {
     // --------------------------------------------------------------------------
-    /// Find and return null terminated byte sequence as a string slice
-    fn str_from_u8_nul_utf8_unchecked(utf8_src: &[u8]) -> &str {
-        let nul_range_end = utf8_src
-            .iter()
-            .position(|&c| c == b'\0')
-            .unwrap_or(utf8_src.len()); // default to length if no `\0` present
-        unsafe { ::std::str::from_utf8_unchecked(&utf8_src[0..nul_range_end]) }
+    /// Creates a new instance of the modinfo accessor for the section with
+    /// the given index. Returns `None` if there is no such section or
+    /// the data of the section or of the section it is linked to fails
+    /// to be read from a lazily loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<ModInfoSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+        let content = parse_entries(elfio.get_section_by_index(index)?.get_data());
+
+        Some(ModInfoSectionAccessorMut {
+            elfio,
+            index,
+            content,
+        })
     }
 
     // --------------------------------------------------------------------------
-    /// Returns the number of modinfo entries
+    /// Returns the number of modinfo entries, including duplicate keys
     pub fn get_entries_num(&self) -> ElfXword {
         self.content.len() as ElfXword
     }
 
     // --------------------------------------------------------------------------
-    /// Get iterator over all modinfo entries
-    pub fn get_iter(&self) -> Iter<String, String> {
+    /// Get iterator over all modinfo entries in the section order
+    pub fn get_iter(&self) -> slice::Iter<'_, (String, String)> {
         self.content.iter()
     }
 
     // --------------------------------------------------------------------------
-    /// Retrieve a value by its key
-    pub fn get(&self, field: &str) -> Option<&String> {
-        self.content.get(field)
+    /// Retrieve the first value with the given key
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.content
+            .iter()
+            .find(|(key, _)| key == field)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // --------------------------------------------------------------------------
+    /// Retrieve all values with the given key in the section order
+    pub fn get_all(&self, field: &str) -> Vec<&str> {
+        self.content
+            .iter()
+            .filter(|(key, _)| key == field)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    // --------------------------------------------------------------------------
+    /// Appends a new entry. Existing entries with the same key are kept
+    pub fn add(&mut self, field: &str, value: &str) {
+        self.content.push((field.to_string(), value.to_string()));
+        self.save();
     }
+
+    // --------------------------------------------------------------------------
+    /// Replaces the value of the first entry with the given key and removes
+    /// the rest of them. The entry is appended if the key is not present
+    pub fn set(&mut self, field: &str, value: &str) {
+        let mut found = false;
+        self.content.retain_mut(|(key, old_value)| {
+            if key != field {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            *old_value = value.to_string();
+            found = true;
+            true
+        });
+
+        if !found {
+            self.content.push((field.to_string(), value.to_string()));
+        }
+        self.save();
+    }
+
+    // --------------------------------------------------------------------------
+    /// Removes all entries with the given key. Returns the number of
+    /// removed entries
+    pub fn remove(&mut self, field: &str) -> ElfXword {
+        let entries_num = self.content.len();
+        self.content.retain(|(key, _)| key != field);
+
+        let removed = entries_num - self.content.len();
+        if removed != 0 {
+            self.save();
+        }
+
+        removed as ElfXword
+    }
+
+    // Serializes the entries back to the section data
+    fn save(&mut self) {
+        let mut data = Vec::new();
+        for (key, value) in &self.content {
+            data.extend_from_slice(key.as_bytes());
+            data.push(b'=');
+            data.extend_from_slice(value.as_bytes());
+            data.push(0);
+        }
+
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index_mut(self.index).unwrap();
+        section.set_data(&data);
+    }
+}
+
+// --------------------------------------------------------------------------
+// Entries are null terminated "key=value" strings. Entries without
+// the '=' separator are malformed and skipped
+fn parse_entries(data: &[u8]) -> Vec<(String, String)> {
+    data.split(|&c| c == b'\0')
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let pos = entry.find('=')?;
+            Some((entry[..pos].to_string(), entry[pos + 1..].to_string()))
+        })
+        .collect()
 }
@@ -82,14 +82,14 @@ pub struct Note {
 /// }
 /// ```
 pub struct NoteSectionAccessor<'a> {
-    elfio:                &'a Elfio,
+    elfio:                &'a Elfio<'a>,
     section:              &'a dyn ElfSectionTrait,
     note_start_positions: Vec<usize>,
 }
 
 impl<'a> NoteSectionAccessor<'a> {
     /// Creates a new instance of the symbol table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> NoteSectionAccessor<'a> {
+    pub fn new(elfio: &'a Elfio<'a>, section: &'a dyn ElfSectionTrait) -> NoteSectionAccessor<'a> {
         let mut note_accessor = NoteSectionAccessor {
             elfio,
             section,
@@ -102,9 +102,9 @@ impl<'a> NoteSectionAccessor<'a> {
             return note_accessor;
         }
 
-        let align = std::mem::size_of::<ElfWord>();
+        let align = get_note_align(section);
         let mut current: usize = 0;
-        while (current + 3 * align) <= size {
+        while (current + NOTE_HEADER_SIZE) <= size {
             note_accessor.note_start_positions.push(current);
 
             let converter = elfio.get_converter();
@@ -116,9 +116,12 @@ impl<'a> NoteSectionAccessor<'a> {
                     .unwrap_or([0x8, 0u8, 0u8, 0u8]),
             ));
 
-            current += 3 * align
-                + ((namesz as usize + align - 1) / align) * align
-                + ((descsz as usize + align - 1) / align) * align;
+            let desc_pos = get_desc_pos(namesz, align);
+            let note_size = (desc_pos + descsz as u64).div_ceil(align) * align;
+            if current as u64 + note_size > size as u64 {
+                break;
+            }
+            current += note_size as usize;
         }
 
         note_accessor
@@ -138,7 +141,7 @@ impl<'a> NoteSectionAccessor<'a> {
         }
 
         let area = &data[self.note_start_positions[index]..];
-        let align = std::mem::size_of::<ElfWord>();
+        let align = get_note_align(self.section);
         let converter = self.elfio.get_converter();
 
         let name_size = converter.convert(u32::from_ne_bytes(
@@ -151,23 +154,117 @@ impl<'a> NoteSectionAccessor<'a> {
             <[u8; 4]>::try_from(&area[8..12]).unwrap_or([0x8, 0u8, 0u8, 0u8]),
         ));
 
-        let max_name_size = data.len() - self.note_start_positions[index];
-        if name_size < 1
-            || name_size > max_name_size as u32
-            || name_size + desc_size > max_name_size as u32
-        {
+        let desc_pos = get_desc_pos(name_size, align);
+        if name_size < 1 || desc_pos + desc_size as u64 > area.len() as u64 {
             return None;
         }
 
         Some(Note {
             ntype,
-            name: unsafe {
-                ::std::str::from_utf8_unchecked(&area[12..12 + name_size as usize - 1]).to_string()
-            },
+            name: String::from_utf8_lossy(&area[12..12 + name_size as usize - 1]).into_owned(),
             description: {
-                let desc_pos = 12 + ((name_size as usize + align - 1) / align) * align;
+                let desc_pos = desc_pos as usize;
                 area[desc_pos..desc_pos + desc_size as usize].to_vec()
             },
         })
     }
 }
+
+/// A section data accessor intended to modify note sections
+///
+/// The name and the descriptor of a note are padded to 8 bytes in sections
+/// having 8 bytes alignment, like `.note.gnu.property`, and to 4 bytes
+/// otherwise.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, NoteSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let note = elf.add_section(".note.gnu.build-id");
+/// note.set_type(constant::SHT_NOTE);
+/// note.set_addr_align(4);
+/// let note_index = note.get_index();
+///
+/// let mut notes = NoteSectionAccessorMut::new(&mut elf, note_index).unwrap();
+/// assert_eq!(notes.add_note(3, "GNU", &[0x12, 0x34, 0x56, 0x78, 0x9a]), 0);
+///
+/// let note = notes.get_note(0).unwrap();
+/// assert_eq!(note.ntype, 3);
+/// assert_eq!(note.name, "GNU");
+/// assert_eq!(note.description, [0x12, 0x34, 0x56, 0x78, 0x9a]);
+///
+/// let section = elf.get_section_by_index(note_index).unwrap();
+/// assert_eq!(section.get_size(), 24);
+/// ```
+pub struct NoteSectionAccessorMut<'a, 'b> {
+    elfio: &'a mut Elfio<'b>,
+    index: ElfWord,
+}
+
+impl<'a, 'b> NoteSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the note section accessor for the section
+    /// with the given index. Returns `None` if there is no such section or
+    /// the data of the section or of the section it is linked to fails
+    /// to be read from a lazily loaded file
+    pub fn new(elfio: &'a mut Elfio<'b>, index: ElfWord) -> Option<NoteSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+
+        Some(NoteSectionAccessorMut { elfio, index })
+    }
+
+    /// Returns number of notes in the section
+    pub fn get_notes_num(&self) -> ElfWord {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        NoteSectionAccessor::new(self.elfio, section).get_notes_num()
+    }
+
+    /// Returns a note by its ordinal number
+    pub fn get_note(&self, index: ElfWord) -> Option<Note> {
+        let section = self.elfio.get_section_by_index(self.index)?;
+        NoteSectionAccessor::new(self.elfio, section).get_note(index)
+    }
+
+    /// Appends a note to the section and returns its ordinal number
+    pub fn add_note(&mut self, ntype: ElfWord, name: &str, description: &[u8]) -> ElfWord {
+        let index = self.get_notes_num();
+        let converter = *self.elfio.get_converter();
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index_mut(self.index).unwrap();
+        let align = get_note_align(section) as usize;
+
+        let mut data = section.get_data().to_vec();
+        data.resize(data.len().div_ceil(align) * align, 0);
+        let start = data.len();
+
+        let name_size = name.len() as ElfWord + 1;
+        data.extend_from_slice(&converter.convert(name_size).to_ne_bytes());
+        data.extend_from_slice(&converter.convert(description.len() as ElfWord).to_ne_bytes());
+        data.extend_from_slice(&converter.convert(ntype).to_ne_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data.resize(start + get_desc_pos(name_size, align as u64) as usize, 0);
+        data.extend_from_slice(description);
+        data.resize(data.len().div_ceil(align) * align, 0);
+        section.set_data(&data);
+
+        index
+    }
+}
+
+// Size of namesz, descsz and type fields of a note
+const NOTE_HEADER_SIZE: usize = 3 * std::mem::size_of::<ElfWord>();
+
+// Returns the alignment of the note fields in the section
+fn get_note_align(section: &dyn ElfSectionTrait) -> u64 {
+    if section.get_addr_align() == 8 {
+        8
+    } else {
+        4
+    }
+}
+
+// Returns the descriptor position relative to the note start
+fn get_desc_pos(name_size: ElfWord, align: u64) -> u64 {
+    (NOTE_HEADER_SIZE as u64 + name_size as u64).div_ceil(align) * align
+}
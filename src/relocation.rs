@@ -32,7 +32,7 @@ use super::utils::Convert;
 /// files to hold the right information for a process's program image.
 ///
 /// See documentation for [RelocationSectionAccessor] for usage example
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Relocation {
     /// A number that determines, along with the originator’s name,
     /// the interpretation of the note contents
@@ -49,6 +49,7 @@ pub struct Relocation {
 
 #[repr(C)]
 #[derive(Default)]
+#[allow(dead_code)]
 struct Elf32Rel {
     r_offset: Elf32Addr,
     r_info:   ElfWord,
@@ -64,6 +65,7 @@ struct Elf32Rela {
 
 #[repr(C)]
 #[derive(Default)]
+#[allow(dead_code)]
 struct Elf64Rel {
     r_offset: Elf64Addr,
     r_info:   ElfXword,
@@ -115,14 +117,14 @@ struct Elf64Rela {
 /// }
 /// ```
 pub struct RelocationSectionAccessor<'a> {
-    elfio:   &'a Elfio,
+    elfio:   &'a Elfio<'a>,
     section: &'a dyn ElfSectionTrait,
 }
 
 impl<'a> RelocationSectionAccessor<'a> {
     /// Creates a new instance of the relocation table accessor
     pub fn new(
-        elfio: &'a Elfio,
+        elfio: &'a Elfio<'a>,
         section: &'a dyn ElfSectionTrait,
     ) -> RelocationSectionAccessor<'a> {
         RelocationSectionAccessor { elfio, section }
@@ -131,7 +133,7 @@ impl<'a> RelocationSectionAccessor<'a> {
     /// Returns number of symbols
     pub fn get_entries_num(&self) -> ElfXword {
         if self.section.get_entry_size() != 0 {
-            return self.section.get_size() / self.section.get_entry_size();
+            return self.section.get_data_size() / self.section.get_entry_size();
         }
 
         0
@@ -146,11 +148,15 @@ impl<'a> RelocationSectionAccessor<'a> {
 
         let offset: usize = (index * self.section.get_entry_size()) as usize;
         let end: usize = offset + self.section.get_entry_size() as usize;
-        let entry_area = &self.section.get_data()[offset..end];
+        let entry_area = self.section.try_get_data().ok()?.get(offset..end)?;
 
         let converter = self.elfio.get_converter();
 
         if self.elfio.get_class() == constant::ELFCLASS64 {
+            if entry_area.len() < self.get_min_entry_size::<Elf64Rel, Elf64Rela>() {
+                return None;
+            }
+
             let mut entry = Elf64Rela {
                 r_offset: converter.convert(u64::from_ne_bytes(
                     <[u8; 8]>::try_from(&entry_area[0..8])
@@ -180,6 +186,10 @@ impl<'a> RelocationSectionAccessor<'a> {
                 },
             })
         } else {
+            if entry_area.len() < self.get_min_entry_size::<Elf32Rel, Elf32Rela>() {
+                return None;
+            }
+
             let mut entry = Elf32Rela {
                 r_offset: converter.convert(u32::from_ne_bytes(
                     <[u8; 4]>::try_from(&entry_area[0..4]).unwrap_or([0u8, 0u8, 0u8, 0u8]),
@@ -207,4 +217,168 @@ impl<'a> RelocationSectionAccessor<'a> {
             })
         }
     }
+
+    // Returns the size of the relocation structure matching the section type
+    fn get_min_entry_size<Rel, Rela>(&self) -> usize {
+        if self.section.get_type() == constant::SHT_RELA {
+            std::mem::size_of::<Rela>()
+        } else {
+            std::mem::size_of::<Rel>()
+        }
+    }
+}
+
+/// A section data accessor intended to modify relocation tables
+///
+/// Both SHT_REL and SHT_RELA sections are supported. The addend is written
+/// for SHT_RELA sections only; a missing addend is written as zero.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, Relocation, RelocationSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2MSB);
+/// let rela = elf.add_section(".rela.text");
+/// rela.set_type(constant::SHT_RELA);
+/// let rela_index = rela.get_index();
+///
+/// let mut relocs = RelocationSectionAccessorMut::new(&mut elf, rela_index).unwrap();
+/// let rel = Relocation {
+///     offset: 0x10,
+///     symbol: 5,
+///     rtype:  1,
+///     addend: Some(-4),
+/// };
+/// assert_eq!(relocs.add_entry(&rel), 0);
+/// assert_eq!(relocs.get_entry(0).unwrap().addend, Some(-4));
+///
+/// let section = elf.get_section_by_index(rela_index).unwrap();
+/// assert_eq!(section.get_entry_size(), 12);
+/// assert_eq!(section.get_data(), [0, 0, 0, 0x10, 0, 0, 5, 1, 0xFF, 0xFF, 0xFF, 0xFC]);
+/// ```
+pub struct RelocationSectionAccessorMut<'a, 'b> {
+    elfio: &'a mut Elfio<'b>,
+    index: ElfWord,
+}
+
+impl<'a, 'b> RelocationSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the relocation table accessor for the section
+    /// with the given index. Returns `None` if there is no such section or
+    /// the section entry size is set and differs from the size of relocation
+    /// entries of the file class, or the data of the section
+    /// or of the section it is linked to fails to be read from a lazily
+    /// loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<RelocationSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+        let section = elfio.get_section_by_index(index)?;
+        // Entries of other sizes can't be written without truncating them
+        let entry_size = section.get_entry_size();
+        if entry_size != 0 && entry_size != get_entry_size(elfio, section) {
+            return None;
+        }
+
+        Some(RelocationSectionAccessorMut { elfio, index })
+    }
+
+    /// Returns number of relocation entries
+    pub fn get_entries_num(&self) -> ElfXword {
+        match self.elfio.get_section_by_index(self.index) {
+            Some(section) => RelocationSectionAccessor::new(self.elfio, section).get_entries_num(),
+            None => 0,
+        }
+    }
+
+    /// Get a relocation entry by its index
+    pub fn get_entry(&self, index: ElfXword) -> Option<Relocation> {
+        let section = self.elfio.get_section_by_index(self.index)?;
+        RelocationSectionAccessor::new(self.elfio, section).get_entry(index)
+    }
+
+    /// Appends a relocation entry to the table and returns its index
+    pub fn add_entry(&mut self, relocation: &Relocation) -> ElfXword {
+        let entry = self.encode(relocation);
+        let section = self.get_section_mut();
+        if section.get_entry_size() == 0 {
+            section.set_entry_size(entry.len() as ElfXword);
+        }
+        let index = section.get_data().len() as ElfXword / section.get_entry_size();
+        section.append_data(&entry);
+
+        index
+    }
+
+    /// Replaces the relocation entry with the given index. Returns `false`
+    /// if there is no such entry
+    pub fn set_entry(&mut self, index: ElfXword, relocation: &Relocation) -> bool {
+        if index >= self.get_entries_num() {
+            return false;
+        }
+
+        let entry = self.encode(relocation);
+        let section = self.get_section_mut();
+        let mut data = section.get_data().to_vec();
+        let offset = (index * section.get_entry_size()) as usize;
+        match data.get_mut(offset..offset + entry.len()) {
+            Some(area) => area.copy_from_slice(&entry),
+            None => return false,
+        }
+        section.set_data(&data);
+
+        true
+    }
+
+    // Converts the relocation to the relocation table entry
+    fn encode(&mut self, relocation: &Relocation) -> Vec<u8> {
+        let converter = *self.elfio.get_converter();
+        let is_64 = self.elfio.get_class() == constant::ELFCLASS64;
+        let section = self.get_section_mut();
+        let is_rela = section.get_type() == constant::SHT_RELA;
+        let addend = relocation.addend.unwrap_or(0);
+
+        let mut entry = Vec::new();
+        if is_64 {
+            let info = ((relocation.symbol as ElfXword) << 32) | relocation.rtype as ElfXword;
+            entry.extend_from_slice(&converter.convert(relocation.offset).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(info).to_ne_bytes());
+            if is_rela {
+                entry.extend_from_slice(&converter.convert(addend).to_ne_bytes());
+            }
+        } else {
+            let info = (relocation.symbol << 8) | (relocation.rtype & 0xFF);
+            entry.extend_from_slice(&converter.convert(relocation.offset as u32).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(info).to_ne_bytes());
+            if is_rela {
+                entry.extend_from_slice(&converter.convert(addend as i32).to_ne_bytes());
+            }
+        }
+
+        entry
+    }
+
+    // The section existence is checked on the accessor creation
+    fn get_section_mut(&mut self) -> &mut dyn ElfSectionTrait {
+        self.elfio.get_section_by_index_mut(self.index).unwrap()
+    }
+}
+
+// --------------------------------------------------------------------------
+// Returns the size of relocation entries of the section for the file class
+fn get_entry_size(elfio: &Elfio, section: &dyn ElfSectionTrait) -> ElfXword {
+    let is_rela = section.get_type() == constant::SHT_RELA;
+    let size = if elfio.get_class() == constant::ELFCLASS64 {
+        if is_rela {
+            std::mem::size_of::<Elf64Rela>()
+        } else {
+            std::mem::size_of::<Elf64Rel>()
+        }
+    } else if is_rela {
+        std::mem::size_of::<Elf32Rela>()
+    } else {
+        std::mem::size_of::<Elf32Rel>()
+    };
+
+    size as ElfXword
 }
@@ -21,7 +21,9 @@ THE SOFTWARE.
 */
 
 use num_traits::{AsPrimitive, Zero};
+use std::cell::RefCell;
 use std::io;
+use std::rc::Rc;
 
 use super::types::*;
 use super::utils::*;
@@ -41,24 +43,46 @@ pub trait ElfSectionAccessTrait {
     ELFIO_GET_SET_ACCESS_DECL!(ElfXword, addr_align);
     ELFIO_GET_SET_ACCESS_DECL!(ElfXword, entry_size);
 
+    /// Returns the section index in the section header table
+    fn get_index(&self) -> ElfWord;
     /// Get section name
     fn get_name(&self) -> &str;
     /// Set section name
     fn set_name(&mut self, name: &str);
-    /// Returns section data
+    /// Returns section data. An empty slice is returned if the data of
+    /// a lazily loaded file fails to be read
     fn get_data(&self) -> &[u8];
-    /// Initialize section data
+    /// Returns section data or the error of reading it from a lazily
+    /// loaded file
+    fn try_get_data(&self) -> io::Result<&[u8]>;
+    /// Returns section data to be modified in place. The section size
+    /// can't be changed this way. The data taken from the file is copied
+    /// on the first call
+    fn get_data_mut(&mut self) -> io::Result<&mut [u8]>;
+    /// Initialize section data. The section size is updated accordingly
     fn set_data(&mut self, data: &[u8]);
+    /// Append data to the section. The section size is updated accordingly
+    fn append_data(&mut self, data: &[u8]);
 }
 
 // --------------------------------------------------------------------------
-pub trait ElfSectionTrait: ElfSectionAccessTrait + Load {}
+pub trait ElfSectionTrait: ElfSectionAccessTrait + Load + Save {
+    fn load_data(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()>;
+    fn set_data_source(&mut self, reader: &Rc<RefCell<dyn ElfioReadSeek>>);
+    fn set_shared_data(&mut self, content: &Rc<Vec<u8>>) -> io::Result<()>;
+    fn save_data(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()>;
+    fn get_data_size(&self) -> ElfXword;
+    fn set_index(&mut self, index: ElfWord);
+    fn is_offset_set(&self) -> bool;
+    fn is_address_set(&self) -> bool;
+    fn reset_layout(&mut self);
+}
 
 // --------------------------------------------------------------------------
 // ELF file header
 #[repr(C)]
 #[derive(Debug)]
-pub struct ElfSection<Addr, Offset, Word> {
+pub struct ElfSection<'a, Addr, Offset, Word> {
     sh_name:      ElfWord,
     sh_type:      ElfWord,
     sh_flags:     Word,
@@ -69,24 +93,34 @@ pub struct ElfSection<Addr, Offset, Word> {
     sh_info:      ElfWord,
     sh_addralign: Word,
     sh_entsize:   Word,
+    index:        ElfWord,
     name:         String,
     converter:    Converter,
-    data:         Vec<u8>,
+    data:         DataStorage<'a>,
+    image:        Option<&'a [u8]>,
+    offset_set:   bool,
+    address_set:  bool,
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> ElfSection<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSection<'a, Addr, Offset, Word>
 where
     Addr: Zero + Load + AsPrimitive<u64>,
     Offset: Zero + Load + AsPrimitive<u64>,
     Word: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
-    pub fn new(conv: &Converter) -> ElfSection<Addr, Offset, Word> {
+    // The section data loaded by `load_data` is borrowed from the ELF file
+    // image, if it is provided, instead of being copied
+    pub fn new(conv: &Converter, image: Option<&'a [u8]>) -> ElfSection<'a, Addr, Offset, Word> {
         Self {
             converter:    *conv,
+            index:        0,
             name:         String::from(""),
-            data:         Vec::new(),
+            data:         DataStorage::Owned(Vec::new()),
+            image,
+            offset_set:   false,
+            address_set:  false,
             sh_name:      0,
             sh_type:      0,
             sh_flags:     Word::zero(),
@@ -103,19 +137,88 @@ where
 
 // --------------------------------------------------------------------------
 /// Section attributes access trait
-impl<Addr, Offset, Word> ElfSectionTrait for ElfSection<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSectionTrait for ElfSection<'a, Addr, Offset, Word>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
-    Addr: Zero + Load + AsPrimitive<u64>,
-    Offset: Zero + Load + AsPrimitive<u64>,
-    Word: Zero + Load + AsPrimitive<u64>,
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
+    Word: Zero + Load + Save + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
+    fn load_data(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
+        self.data = DataStorage::Owned(Vec::new());
+        if self.get_type() == constant::SHT_NULL || self.get_type() == constant::SHT_NOBITS {
+            return Ok(());
+        }
+
+        self.data = DataStorage::load(reader, self.image, self.get_offset(), self.get_size())?;
+
+        Ok(())
+    }
+
+    // The data is read from the reader on the first get_data() call.
+    // The current offset and size are remembered, so the data is found
+    // even if the section is moved by the file layout before that
+    fn set_data_source(&mut self, reader: &Rc<RefCell<dyn ElfioReadSeek>>) {
+        self.data = DataStorage::Owned(Vec::new());
+        if self.get_type() != constant::SHT_NULL && self.get_type() != constant::SHT_NOBITS {
+            self.data = DataStorage::lazy(reader, self.get_offset(), self.get_size());
+        }
+    }
+
+    fn set_shared_data(&mut self, content: &Rc<Vec<u8>>) -> io::Result<()> {
+        self.data = DataStorage::Owned(Vec::new());
+        if self.get_type() == constant::SHT_NULL || self.get_type() == constant::SHT_NOBITS {
+            return Ok(());
+        }
+
+        self.data = DataStorage::shared(content, self.get_offset(), self.get_size())?;
+
+        Ok(())
+    }
+
+    fn save_data(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        if self.get_type() != constant::SHT_NULL
+            && self.get_type() != constant::SHT_NOBITS
+        {
+            let data = self.try_get_data()?;
+            if !data.is_empty() {
+                writer.seek(io::SeekFrom::Start(self.get_offset()))?;
+                writer.write_all(data)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Number of section bytes available for accessors. It is less than
+    // the section size for SHT_NOBITS sections
+    fn get_data_size(&self) -> ElfXword {
+        std::cmp::min(self.get_size(), self.get_data().len() as ElfXword)
+    }
+
+    fn set_index(&mut self, index: ElfWord) {
+        self.index = index;
+    }
+
+    fn is_offset_set(&self) -> bool {
+        self.offset_set
+    }
+
+    fn is_address_set(&self) -> bool {
+        self.address_set
+    }
+
+    // The offset and the address are assigned by the file layout anew
+    fn reset_layout(&mut self) {
+        self.offset_set = false;
+        self.address_set = false;
+    }
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> ElfSectionAccessTrait for ElfSection<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSectionAccessTrait for ElfSection<'a, Addr, Offset, Word>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
@@ -127,14 +230,28 @@ where
     ELFIO_GET_SET_ACCESS!(ElfWord, name_string_offset, sh_name);
     ELFIO_GET_SET_ACCESS!(ElfWord, type, sh_type);
     ELFIO_GET_SET_ACCESS!(ElfXword, flags, sh_flags);
-    ELFIO_GET_SET_ACCESS!(Elf64Addr, address, sh_addr);
-    ELFIO_GET_SET_ACCESS!(Elf64Off, offset, sh_offset);
+    ELFIO_GET_ACCESS!(Elf64Addr, address, sh_addr);
+    ELFIO_GET_ACCESS!(Elf64Off, offset, sh_offset);
     ELFIO_GET_SET_ACCESS!(ElfXword, size, sh_size);
     ELFIO_GET_SET_ACCESS!(ElfWord, link, sh_link);
     ELFIO_GET_SET_ACCESS!(ElfWord, info, sh_info);
     ELFIO_GET_SET_ACCESS!(ElfXword, addr_align, sh_addralign);
     ELFIO_GET_SET_ACCESS!(ElfXword, entry_size, sh_entsize);
 
+    fn set_address(&mut self, value: Elf64Addr) {
+        self.sh_addr = self.converter.convert(value.as_());
+        self.address_set = true;
+    }
+
+    fn set_offset(&mut self, value: Elf64Off) {
+        self.sh_offset = self.converter.convert(value.as_());
+        self.offset_set = true;
+    }
+
+    fn get_index(&self) -> ElfWord {
+        self.index
+    }
+
     fn get_name(&self) -> &str {
         &self.name
     }
@@ -144,16 +261,38 @@ where
     }
 
     fn get_data(&self) -> &[u8] {
-        &self.data
+        self.data.get()
+    }
+
+    fn try_get_data(&self) -> io::Result<&[u8]> {
+        self.data.try_get()
+    }
+
+    fn get_data_mut(&mut self) -> io::Result<&mut [u8]> {
+        self.data.get_mut()
     }
 
     fn set_data(&mut self, data: &[u8]) {
-        self.data = data.to_vec();
+        self.data = DataStorage::Owned(data.to_vec());
+        self.set_size(data.len() as ElfXword);
+    }
+
+    fn append_data(&mut self, data: &[u8]) {
+        if let DataStorage::Owned(owned) = &mut self.data {
+            owned.extend_from_slice(data);
+        } else {
+            let mut owned = self.get_data().to_vec();
+            owned.extend_from_slice(data);
+            self.data = DataStorage::Owned(owned);
+        }
+
+        let size = self.get_data().len() as ElfXword;
+        self.set_size(size);
     }
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> Load for ElfSection<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> Load for ElfSection<'a, Addr, Offset, Word>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
@@ -162,7 +301,7 @@ where
     Word: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
-    fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
         self.sh_name.load(reader)?;
         self.sh_type.load(reader)?;
         self.sh_flags.load(reader)?;
@@ -173,12 +312,34 @@ where
         self.sh_info.load(reader)?;
         self.sh_addralign.load(reader)?;
         self.sh_entsize.load(reader)?;
+        self.offset_set = true;
+        self.address_set = true;
 
-        self.data = vec![0; self.get_size().as_()];
-        if self.get_type() != constant::SHT_NULL && self.get_type() != constant::SHT_NOBITS {
-            reader.seek(io::SeekFrom::Start(self.get_offset()))?;
-            reader.read_exact(&mut self.data)?;
-        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------------------------------
+impl<'a, Addr, Offset, Word> Save for ElfSection<'a, Addr, Offset, Word>
+where
+    u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
+    u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
+    Word: Zero + Load + Save + AsPrimitive<u64>,
+    Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
+{
+    fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        self.sh_name.save(writer)?;
+        self.sh_type.save(writer)?;
+        self.sh_flags.save(writer)?;
+        self.sh_addr.save(writer)?;
+        self.sh_offset.save(writer)?;
+        self.sh_size.save(writer)?;
+        self.sh_link.save(writer)?;
+        self.sh_info.save(writer)?;
+        self.sh_addralign.save(writer)?;
+        self.sh_entsize.save(writer)?;
 
         Ok(())
     }
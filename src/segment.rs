@@ -21,7 +21,9 @@ THE SOFTWARE.
 */
 
 use num_traits::{AsPrimitive, Zero};
+use std::cell::RefCell;
 use std::io;
+use std::rc::Rc;
 
 use super::types::*;
 use super::utils::*;
@@ -31,45 +33,99 @@ use super::utils::*;
 pub trait ElfSegmentAccessTrait {
     ELFIO_GET_SET_ACCESS_DECL!(ElfWord, type);
     ELFIO_GET_SET_ACCESS_DECL!(ElfWord, flags);
-    ELFIO_GET_ACCESS_DECL!(Elf64Off, offset);
+    ELFIO_GET_SET_ACCESS_DECL!(Elf64Off, offset);
     ELFIO_GET_SET_ACCESS_DECL!(Elf64Addr, virtual_address);
     ELFIO_GET_SET_ACCESS_DECL!(Elf64Addr, physical_address);
     ELFIO_GET_SET_ACCESS_DECL!(ElfXword, file_size);
     ELFIO_GET_SET_ACCESS_DECL!(ElfXword, memory_size);
     ELFIO_GET_SET_ACCESS_DECL!(ElfXword, align);
+
+    /// Returns segment data. These are `p_filesz` bytes of the file
+    /// at `p_offset`. Modifications of the sections belonging to
+    /// the segment are not reflected in the segment data. An empty slice
+    /// is returned if the data of a lazily loaded file fails to be read
+    fn get_data(&self) -> &[u8];
+    /// Returns segment data or the error of reading it from a lazily
+    /// loaded file
+    fn try_get_data(&self) -> io::Result<&[u8]>;
+    /// Returns segment data to be modified in place. The segment file
+    /// size can't be changed this way. The data taken from the file is
+    /// copied on the first call
+    fn get_data_mut(&mut self) -> io::Result<&mut [u8]>;
+    /// Initialize segment data. The segment file size is updated
+    /// accordingly, the memory size is increased up to the file size
+    fn set_data(&mut self, data: &[u8]);
+    /// Returns segment data and the number of zero bytes following it
+    /// in memory up to the segment memory size. The zero bytes are not
+    /// allocated as the memory size is taken from the file as is
+    fn get_memory_image(&self) -> (&[u8], ElfXword);
+    /// Returns indexes of the sections belonging to the segment. For
+    /// the segments loaded from a file, the sections are found by their
+    /// file offsets and addresses the same way as `readelf -l` does
+    fn get_section_indices(&self) -> &[ElfWord];
 }
 
 // --------------------------------------------------------------------------
-pub trait ElfSegmentTrait: ElfSegmentAccessTrait + Load {}
+pub trait ElfSegmentTrait: ElfSegmentAccessTrait + Load + Save {
+    fn add_section_index(&mut self, index: ElfWord, addr_align: ElfXword);
+    fn remove_section_index(&mut self, index: ElfWord);
+    fn load_data(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()>;
+    fn set_data_source(&mut self, reader: &Rc<RefCell<dyn ElfioReadSeek>>);
+    fn set_shared_data(&mut self, content: &Rc<Vec<u8>>) -> io::Result<()>;
+    fn save_data(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()>;
+    fn is_offset_set(&self) -> bool;
+    fn is_address_set(&self) -> bool;
+    fn is_layout_needed(&self) -> bool;
+    fn set_layout_done(&mut self);
+    fn reset_layout(&mut self);
+}
 
 // --------------------------------------------------------------------------
 // ELF file header
 #[repr(C)]
-pub struct ElfSegment<Addr, Offset, Word> {
-    p_type:    ElfWord,
-    p_flags:   ElfWord,
-    p_offset:  Offset,
-    p_vaddr:   Addr,
-    p_paddr:   Addr,
-    p_filesz:  Word,
-    p_memsz:   Word,
-    p_align:   Word,
-    converter: Converter,
-    class:     u8,
+pub struct ElfSegment<'a, Addr, Offset, Word> {
+    p_type:         ElfWord,
+    p_flags:        ElfWord,
+    p_offset:       Offset,
+    p_vaddr:        Addr,
+    p_paddr:        Addr,
+    p_filesz:       Word,
+    p_memsz:        Word,
+    p_align:        Word,
+    converter:      Converter,
+    class:          u8,
+    sections:       Vec<ElfWord>,
+    data:           DataStorage<'a>,
+    image:          Option<&'a [u8]>,
+    offset_set:     bool,
+    address_set:    bool,
+    sections_added: bool,
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> ElfSegment<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSegment<'a, Addr, Offset, Word>
 where
     Addr: Zero + Load + AsPrimitive<u64>,
     Offset: Zero + Load + AsPrimitive<u64>,
     Word: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
-    pub fn new(conv: &Converter, class: u8) -> ElfSegment<Addr, Offset, Word> {
+    // The segment data loaded by `load_data` is borrowed from the ELF file
+    // image, if it is provided, instead of being copied
+    pub fn new(
+        conv: &Converter,
+        class: u8,
+        image: Option<&'a [u8]>,
+    ) -> ElfSegment<'a, Addr, Offset, Word> {
         Self {
             converter: *conv,
             class,
+            sections: Vec::new(),
+            data: DataStorage::Owned(Vec::new()),
+            image,
+            offset_set: false,
+            address_set: false,
+            sections_added: false,
             p_type: 0,
             p_flags: 0,
             p_offset: Offset::zero(),
@@ -83,19 +139,84 @@ where
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> ElfSegmentTrait for ElfSegment<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSegmentTrait for ElfSegment<'a, Addr, Offset, Word>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
-    Addr: Zero + Load + AsPrimitive<u64>,
-    Offset: Zero + Load + AsPrimitive<u64>,
-    Word: Zero + Load + AsPrimitive<u64>,
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
+    Word: Zero + Load + Save + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
+    // The segment alignment is increased up to the section alignment
+    fn add_section_index(&mut self, index: ElfWord, addr_align: ElfXword) {
+        self.sections.push(index);
+        self.sections_added = true;
+        if addr_align > self.get_align() {
+            self.set_align(addr_align);
+        }
+    }
+
+    fn remove_section_index(&mut self, index: ElfWord) {
+        self.sections.retain(|section| *section != index);
+    }
+
+    fn load_data(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
+        self.data = DataStorage::load(reader, self.image, self.get_offset(), self.get_file_size())?;
+
+        Ok(())
+    }
+
+    // The data is read from the reader on the first get_data() call
+    fn set_data_source(&mut self, reader: &Rc<RefCell<dyn ElfioReadSeek>>) {
+        self.data = DataStorage::lazy(reader, self.get_offset(), self.get_file_size());
+    }
+
+    fn set_shared_data(&mut self, content: &Rc<Vec<u8>>) -> io::Result<()> {
+        self.data = DataStorage::shared(content, self.get_offset(), self.get_file_size())?;
+
+        Ok(())
+    }
+
+    fn save_data(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        let data = self.try_get_data()?;
+        if !data.is_empty() {
+            writer.seek(io::SeekFrom::Start(self.get_offset()))?;
+            writer.write_all(data)?;
+        }
+
+        Ok(())
+    }
+
+    fn is_offset_set(&self) -> bool {
+        self.offset_set
+    }
+
+    fn is_address_set(&self) -> bool {
+        self.address_set
+    }
+
+    // The segment is laid out when it is added or got new sections
+    fn is_layout_needed(&self) -> bool {
+        !self.offset_set || self.sections_added
+    }
+
+    fn set_layout_done(&mut self) {
+        self.sections_added = false;
+    }
+
+    // The offset and the address are taken from the sections by the file
+    // layout anew. The data read from the file doesn't describe the segment
+    // anymore, so it is dropped
+    fn reset_layout(&mut self) {
+        self.offset_set = false;
+        self.address_set = false;
+        self.data = DataStorage::Owned(Vec::new());
+    }
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> ElfSegmentAccessTrait for ElfSegment<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> ElfSegmentAccessTrait for ElfSegment<'a, Addr, Offset, Word>
 where
     u32: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
     u64: AsPrimitive<Addr> + AsPrimitive<Offset> + AsPrimitive<Word>,
@@ -107,22 +228,63 @@ where
     ELFIO_GET_SET_ACCESS!(ElfWord, type, p_type);
     ELFIO_GET_SET_ACCESS!(ElfWord, flags, p_flags);
     ELFIO_GET_ACCESS!(Elf64Off, offset, p_offset);
-    ELFIO_GET_SET_ACCESS!(Elf64Addr, virtual_address, p_vaddr);
+    ELFIO_GET_ACCESS!(Elf64Addr, virtual_address, p_vaddr);
     ELFIO_GET_SET_ACCESS!(Elf64Addr, physical_address, p_paddr);
     ELFIO_GET_SET_ACCESS!(ElfXword, file_size, p_filesz);
     ELFIO_GET_SET_ACCESS!(ElfXword, memory_size, p_memsz);
     ELFIO_GET_SET_ACCESS!(ElfXword, align, p_align);
+
+    fn set_offset(&mut self, value: Elf64Off) {
+        self.p_offset = self.converter.convert(value.as_());
+        self.offset_set = true;
+    }
+
+    fn set_virtual_address(&mut self, value: Elf64Addr) {
+        self.p_vaddr = self.converter.convert(value.as_());
+        self.address_set = true;
+    }
+
+    fn get_section_indices(&self) -> &[ElfWord] {
+        &self.sections
+    }
+
+    fn get_data(&self) -> &[u8] {
+        self.data.get()
+    }
+
+    fn try_get_data(&self) -> io::Result<&[u8]> {
+        self.data.try_get()
+    }
+
+    fn get_data_mut(&mut self) -> io::Result<&mut [u8]> {
+        self.data.get_mut()
+    }
+
+    fn set_data(&mut self, data: &[u8]) {
+        self.data = DataStorage::Owned(data.to_vec());
+        let size = data.len() as ElfXword;
+        self.set_file_size(size);
+        if self.get_memory_size() < size {
+            self.set_memory_size(size);
+        }
+    }
+
+    fn get_memory_image(&self) -> (&[u8], ElfXword) {
+        let data = self.get_data();
+        let zeros = self.get_memory_size().saturating_sub(data.len() as ElfXword);
+        (data, zeros)
+    }
 }
 
 // --------------------------------------------------------------------------
-impl<Addr, Offset, Word> Load for ElfSegment<Addr, Offset, Word>
+impl<'a, Addr, Offset, Word> Load for ElfSegment<'a, Addr, Offset, Word>
 where
     Addr: Zero + Load + AsPrimitive<u64>,
     Offset: Zero + Load + AsPrimitive<u64>,
     Word: Zero + Load + AsPrimitive<u64>,
     Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
 {
-    fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+    fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
         self.p_type.load(reader)?;
         if self.class == constant::ELFCLASS64 {
             self.p_flags.load(reader)?;
@@ -140,6 +302,39 @@ where
             self.p_flags.load(reader)?;
         }
         self.p_align.load(reader)?;
+        self.offset_set = true;
+        self.address_set = true;
+
+        Ok(())
+    }
+}
+
+// --------------------------------------------------------------------------
+impl<'a, Addr, Offset, Word> Save for ElfSegment<'a, Addr, Offset, Word>
+where
+    Addr: Zero + Load + Save + AsPrimitive<u64>,
+    Offset: Zero + Load + Save + AsPrimitive<u64>,
+    Word: Zero + Load + Save + AsPrimitive<u64>,
+    Converter: Convert<Addr> + Convert<Offset> + Convert<Word>,
+{
+    fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        self.p_type.save(writer)?;
+        if self.class == constant::ELFCLASS64 {
+            self.p_flags.save(writer)?;
+            self.p_offset.save(writer)?;
+            self.p_vaddr.save(writer)?;
+            self.p_paddr.save(writer)?;
+            self.p_filesz.save(writer)?;
+            self.p_memsz.save(writer)?;
+        } else {
+            self.p_offset.save(writer)?;
+            self.p_vaddr.save(writer)?;
+            self.p_paddr.save(writer)?;
+            self.p_filesz.save(writer)?;
+            self.p_memsz.save(writer)?;
+            self.p_flags.save(writer)?;
+        }
+        self.p_align.save(writer)?;
 
         Ok(())
     }
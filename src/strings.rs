@@ -20,10 +20,13 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 THE SOFTWARE.
 */
 
+use std::collections::HashMap;
+
 use super::elfio::*;
 use super::section::*;
 use super::types::*;
 
+// --------------------------------------------------------------------------
 /// A section data accessor intended to string tables
 ///
 /// For example:
@@ -54,10 +57,8 @@ use super::types::*;
 ///     Ok(())
 /// }
 /// ```
-
-// --------------------------------------------------------------------------
 pub struct StringSectionAccessor<'a> {
-    _elfio:  &'a Elfio,
+    _elfio:  &'a Elfio<'a>,
     section: &'a dyn ElfSectionTrait,
 }
 
@@ -65,7 +66,10 @@ pub struct StringSectionAccessor<'a> {
 impl<'a> StringSectionAccessor<'a> {
     // --------------------------------------------------------------------------
     /// Creates a new instance of the string table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> StringSectionAccessor<'a> {
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> StringSectionAccessor<'a> {
         StringSectionAccessor {
             _elfio: elfio,
             section,
@@ -73,19 +77,180 @@ impl<'a> StringSectionAccessor<'a> {
     }
 
     // --------------------------------------------------------------------------
-    /// Find and return null terminated byte sequence as a string slice
-    fn str_from_u8_nul_utf8_unchecked(utf8_src: &[u8]) -> &str {
-        let nul_range_end = utf8_src
-            .iter()
-            .position(|&c| c == b'\0')
-            .unwrap_or(utf8_src.len()); // default to length if no `\0` present
-        unsafe { ::std::str::from_utf8_unchecked(&utf8_src[0..nul_range_end]) }
+    /// Returns a string from string table by its index. An empty string is
+    /// returned when the index is out of the section data
+    pub fn get_string(&self, index: ElfWord) -> String {
+        get_string(self.section.get_data(), index)
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to modify string tables
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, StringSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let strtab = elf.add_section(".strtab");
+/// strtab.set_type(constant::SHT_STRTAB);
+///
+/// let mut strtab = StringSectionAccessorMut::new(strtab);
+/// assert_eq!(strtab.add_string("domain"), 1);
+/// assert_eq!(strtab.find_or_add_string("main"), 3);
+/// assert_eq!(strtab.find_or_add_string("printf"), 8);
+/// assert_eq!(strtab.get_string(3), "main");
+/// ```
+pub struct StringSectionAccessorMut<'a> {
+    section: &'a mut dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> StringSectionAccessorMut<'a> {
+    // --------------------------------------------------------------------------
+    /// Creates a new instance of the string table accessor
+    pub fn new(section: &'a mut dyn ElfSectionTrait) -> StringSectionAccessorMut<'a> {
+        StringSectionAccessorMut { section }
     }
 
     // --------------------------------------------------------------------------
-    /// Returns a string from string table by its index
+    /// Returns a string from string table by its index. An empty string is
+    /// returned when the index is out of the section data
     pub fn get_string(&self, index: ElfWord) -> String {
-        let strdata = self.section.get_data();
-        Self::str_from_u8_nul_utf8_unchecked(&strdata[index as usize..]).to_string()
+        get_string(self.section.get_data(), index)
+    }
+
+    // --------------------------------------------------------------------------
+    /// Appends a null terminated string to the string table and returns
+    /// its index. The empty string is added first to an empty table, so
+    /// index 0 always refers to the empty string
+    pub fn add_string(&mut self, string: &str) -> ElfWord {
+        self.add_empty_string();
+
+        let index = self.section.get_data().len() as ElfWord;
+        self.section.append_data(string.as_bytes());
+        self.section.append_data(&[0]);
+
+        index
+    }
+
+    // --------------------------------------------------------------------------
+    /// Returns the index of the string if the table already contains it,
+    /// either as a whole string or as a suffix of another string.
+    /// Otherwise, the string is appended to the table
+    pub fn find_or_add_string(&mut self, string: &str) -> ElfWord {
+        self.add_empty_string();
+
+        let mut entry = string.as_bytes().to_vec();
+        entry.push(0);
+
+        let data = self.section.get_data();
+        match data.windows(entry.len()).position(|window| window == &entry[..]) {
+            Some(index) => index as ElfWord,
+            None => self.add_string(string),
+        }
+    }
+
+    // Adds the empty string to an empty table
+    fn add_empty_string(&mut self) {
+        if self.section.get_data().is_empty() {
+            self.section.append_data(&[0]);
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+// Offsets of the strings of a string table and of their suffixes. It lets
+// accessors adding many strings to the same table avoid scanning the table
+// for every string. The strings appended to the table are indexed on demand.
+// The strings aren't copied, the offsets are keyed by the string hashes and
+// the table data is compared on lookup, so hash collisions are harmless
+#[derive(Default)]
+pub(crate) struct StringIndex {
+    offsets: HashMap<u64, ElfWord>,
+    indexed: usize,
+}
+
+impl StringIndex {
+    // Does the same as `StringSectionAccessorMut::find_or_add_string`
+    pub(crate) fn find_or_add_string(
+        &mut self,
+        section: &mut dyn ElfSectionTrait,
+        string: &str,
+    ) -> ElfWord {
+        let mut strtab = StringSectionAccessorMut::new(section);
+        strtab.add_empty_string();
+
+        match self.find_string(strtab.section.get_data(), string) {
+            Some(index) => index,
+            None => strtab.add_string(string),
+        }
+    }
+
+    // Returns the index of the string if the table data contains it
+    pub(crate) fn find_string(&mut self, data: &[u8], string: &str) -> Option<ElfWord> {
+        self.update(data);
+
+        let string = string.as_bytes();
+        let hash = string.iter().rev().fold(0, |hash, &c| next_hash(hash, c));
+        let offset = *self.offsets.get(&finish_hash(hash, string.len()))? as usize;
+        let end = offset.checked_add(string.len())?;
+        match (data.get(offset..end), data.get(end)) {
+            (Some(found), Some(b'\0')) if found == string => Some(offset as ElfWord),
+            _ => None,
+        }
+    }
+
+    // Indexes the strings added since the last update. The first occurrence
+    // of a string wins, as it does for the table scanning. The suffix hashes
+    // are computed from the string end, so each string is hashed once
+    fn update(&mut self, data: &[u8]) {
+        if data.len() < self.indexed {
+            *self = StringIndex::default();
+        }
+
+        let mut start = self.indexed;
+        while let Some(length) = data[start..].iter().position(|&c| c == b'\0') {
+            let mut hash = 0;
+            for suffix in (0..=length).rev() {
+                if suffix < length {
+                    hash = next_hash(hash, data[start + suffix]);
+                }
+                self.offsets
+                    .entry(finish_hash(hash, length - suffix))
+                    .or_insert((start + suffix) as ElfWord);
+            }
+            start += length + 1;
+        }
+        self.indexed = start;
+    }
+}
+
+// Extends the hash of a string suffix by the preceding character
+fn next_hash(hash: u64, c: u8) -> u64 {
+    hash.wrapping_mul(0x0100_0000_01B3).wrapping_add(u64::from(c) + 1)
+}
+
+// Mixes the string length into the hash and spreads the bits
+fn finish_hash(hash: u64, length: usize) -> u64 {
+    let mut hash = hash ^ (length as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^ (hash >> 31)
+}
+
+// --------------------------------------------------------------------------
+// Find and return null terminated byte sequence as a string. Invalid
+// UTF-8 sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`
+fn get_string(data: &[u8], index: ElfWord) -> String {
+    match data.get(index as usize..) {
+        Some(strdata) => {
+            let nul_range_end = strdata
+                .iter()
+                .position(|&c| c == b'\0')
+                .unwrap_or(strdata.len()); // default to length if no `\0` present
+            String::from_utf8_lossy(&strdata[0..nul_range_end]).into_owned()
+        }
+        None => String::new(),
     }
 }
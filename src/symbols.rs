@@ -20,35 +20,63 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 THE SOFTWARE.
 */
 
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
 use super::elfio::*;
+use super::hash::*;
 use super::section::*;
 use super::strings::*;
 use super::types::*;
 use super::utils::Convert;
+use super::versym::*;
 
 // --------------------------------------------------------------------------
 /// A struct represents a single symbol from symbol table section
 ///
 /// See documentation for [SymbolSectionAccessor] for usage example
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Symbol {
     /// The name of the associated symbol
-    pub name:  String,
+    pub name:          String,
     /// The value of the associated symbol
-    pub value: Elf64Addr,
+    pub value:         Elf64Addr,
     /// The symbol's associated size
-    pub size:  ElfXword,
+    pub size:          ElfXword,
     /// This member specifies the symbol's binding attribute
-    pub bind:  u8,
+    pub bind:          u8,
     /// This member specifies the symbol's type attribute
-    pub stype: u8,
+    pub stype:         u8,
     /// This member specifies a symbol's visibility
-    pub other: u8,
+    pub other:         u8,
     /// Every symbol table entry is defined in relation to some section.
     /// This member holds the relevant section header table index.
-    pub shndx: ElfHalf,
+    /// `SHN_XINDEX` is resolved through the extended section index table
+    /// (`SHT_SYMTAB_SHNDX`) linked to the symbol table. Special indices,
+    /// such as `SHN_ABS`, are kept as is and marked by `special_shndx`
+    pub shndx:         ElfWord,
+    /// `shndx` is a special section index from the reserved range, such as
+    /// `SHN_ABS` or `SHN_COMMON`, rather than an index of a section. Indices
+    /// of sections in the reserved range are stored in the extended section
+    /// index table when the flag is not set
+    pub special_shndx: bool,
+    /// The symbol version. It is available for dynamic symbols of files
+    /// with symbol versioning information
+    pub version:       Option<SymbolVersion>,
+}
+
+// --------------------------------------------------------------------------
+/// A symbol version
+///
+/// See documentation for [Symbol]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolVersion {
+    /// The version name, for example, "GLIBC_2.2.5"
+    pub name:   String,
+    /// A hidden version can't be used by default, it is printed as `sym@VER`
+    /// by readelf. Default versions are printed as `sym@@VER`
+    pub hidden: bool,
 }
 
 // --------------------------------------------------------------------------
@@ -110,25 +138,57 @@ struct Elf64Sym {
 ///     assert_eq!(sym.shndx, 23);
 ///     assert_eq!(sym.name, "dtor_idx.5805");
 ///
+///     assert_eq!(symtab.find_by_name("dtor_idx.5805").unwrap().0, 30);
+///     assert_eq!(symtab.find_by_address(0x0804958a).unwrap().1.name, "dtor_idx.5805");
+///
 ///     Ok(())
 /// }
 /// ```
 pub struct SymbolSectionAccessor<'a> {
-    elfio:   &'a Elfio,
-    section: &'a dyn ElfSectionTrait,
+    elfio:     &'a Elfio<'a>,
+    section:   &'a dyn ElfSectionTrait,
+    addresses: OnceCell<Vec<AddressRange>>,
+    versions:  OnceCell<Versions<'a>>,
+    extended:  OnceCell<Option<&'a dyn ElfSectionTrait>>,
+}
+
+// Address range of a defined symbol. `max_end` is the largest range end
+// among this and all preceding ranges sorted by the start address
+struct AddressRange {
+    start:   Elf64Addr,
+    end:     Elf64Addr,
+    max_end: Elf64Addr,
+    index:   ElfXword,
+}
+
+// The symbol version table linked to the symbol table and the version
+// names by their indices taken from the version definitions and
+// requirements
+struct Versions<'a> {
+    versym: Option<&'a dyn ElfSectionTrait>,
+    names:  HashMap<ElfHalf, String>,
 }
 
 // --------------------------------------------------------------------------
 impl<'a> SymbolSectionAccessor<'a> {
     /// Creates a new instance of the symbol table accessor
-    pub fn new(elfio: &'a Elfio, section: &'a dyn ElfSectionTrait) -> SymbolSectionAccessor<'a> {
-        SymbolSectionAccessor { elfio, section }
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> SymbolSectionAccessor<'a> {
+        SymbolSectionAccessor {
+            elfio,
+            section,
+            addresses: OnceCell::new(),
+            versions: OnceCell::new(),
+            extended: OnceCell::new(),
+        }
     }
 
     /// Returns number of symbols
     pub fn get_symbols_num(&self) -> ElfXword {
         if self.section.get_entry_size() != 0 {
-            return self.section.get_size() / self.section.get_entry_size();
+            return self.section.get_data_size() / self.section.get_entry_size();
         }
 
         0
@@ -143,11 +203,15 @@ impl<'a> SymbolSectionAccessor<'a> {
 
         let offset: usize = (index * self.section.get_entry_size()) as usize;
         let end: usize = offset + self.section.get_entry_size() as usize;
-        let symbol_area = &self.section.get_data()[offset..end];
+        let symbol_area = self.section.try_get_data().ok()?.get(offset..end)?;
 
         let converter = self.elfio.get_converter();
 
         if self.elfio.get_class() == constant::ELFCLASS64 {
+            if symbol_area.len() < std::mem::size_of::<Elf64Sym>() {
+                return None;
+            }
+
             let sym = Elf64Sym {
                 st_name:  converter.convert(u32::from_ne_bytes(
                     <[u8; 4]>::try_from(&symbol_area[0..4]).unwrap_or([0u8, 0u8, 0u8, 0u8]),
@@ -167,11 +231,8 @@ impl<'a> SymbolSectionAccessor<'a> {
                 )),
             };
 
-            let string_section = self
-                .elfio
-                .get_section_by_index(self.section.get_link() as ElfHalf);
-            let string_accessor = StringSectionAccessor::new(self.elfio, string_section.unwrap());
-            let name = string_accessor.get_string(sym.st_name);
+            let name = self.get_symbol_name(sym.st_name);
+            let (shndx, special_shndx) = self.get_section_index(index, sym.st_shndx);
 
             Some(Symbol {
                 name,
@@ -180,9 +241,15 @@ impl<'a> SymbolSectionAccessor<'a> {
                 bind: sym.st_info >> 4,
                 stype: sym.st_info & 0xF,
                 other: sym.st_other,
-                shndx: sym.st_shndx,
+                shndx,
+                special_shndx,
+                version: self.get_symbol_version(index),
             })
         } else {
+            if symbol_area.len() < std::mem::size_of::<Elf32Sym>() {
+                return None;
+            }
+
             let sym = Elf32Sym {
                 st_name:  converter.convert(u32::from_ne_bytes(
                     <[u8; 4]>::try_from(&symbol_area[0..4]).unwrap_or([0u8, 0u8, 0u8, 0u8]),
@@ -200,11 +267,8 @@ impl<'a> SymbolSectionAccessor<'a> {
                 )),
             };
 
-            let string_section = self
-                .elfio
-                .get_section_by_index(self.section.get_link() as ElfHalf);
-            let string_accessor = StringSectionAccessor::new(self.elfio, string_section.unwrap());
-            let name = string_accessor.get_string(sym.st_name);
+            let name = self.get_symbol_name(sym.st_name);
+            let (shndx, special_shndx) = self.get_section_index(index, sym.st_shndx);
 
             Some(Symbol {
                 name,
@@ -213,8 +277,644 @@ impl<'a> SymbolSectionAccessor<'a> {
                 bind: sym.st_info >> 4,
                 stype: sym.st_info & 0xF,
                 other: sym.st_other,
-                shndx: sym.st_shndx,
+                shndx,
+                special_shndx,
+                version: self.get_symbol_version(index),
+            })
+        }
+    }
+
+    /// Finds a symbol by its name. The symbol hash table linked to this
+    /// symbol table is used when it is present. Otherwise, the symbols
+    /// are scanned one by one. GNU hash tables don't contain undefined
+    /// symbols, so the symbols are scanned when the lookup fails
+    pub fn find_by_name(&self, name: &str) -> Option<(ElfXword, Symbol)> {
+        let index = self.section.get_index();
+        let hash_section = self.elfio.get_sections().iter().find(|section| {
+            (section.get_type() == constant::SHT_GNU_HASH
+                || section.get_type() == constant::SHT_HASH)
+                && section.get_link() == index
+        });
+
+        let found = match hash_section {
+            Some(hash) if hash.get_type() == constant::SHT_GNU_HASH => {
+                GnuHashSectionAccessor::new(self.elfio, &**hash).find_symbol(name)
+            }
+            Some(hash) => return HashSectionAccessor::new(self.elfio, &**hash).find_symbol(name),
+            None => None,
+        };
+
+        // Undefined symbols are not hashed in GNU hash tables
+        found.or_else(|| {
+            (0..self.get_symbols_num())
+                .filter_map(|index| self.get_symbol(index).map(|symbol| (index, symbol)))
+                .find(|(_, symbol)| symbol.name == name)
+        })
+    }
+
+    /// Finds a defined symbol whose range `[value, value + size)` covers
+    /// the address. When several symbols cover the address, the one with
+    /// the highest start address is returned. The symbols are sorted by
+    /// their addresses on the first call
+    pub fn find_by_address(&self, address: Elf64Addr) -> Option<(ElfXword, Symbol)> {
+        let addresses = self.addresses.get_or_init(|| self.get_address_ranges());
+
+        let pos = addresses.partition_point(|range| range.start <= address);
+        let range = addresses[..pos]
+            .iter()
+            .rev()
+            .take_while(|range| range.max_end > address)
+            .find(|range| range.end > address)?;
+
+        self.get_symbol(range.index)
+            .map(|symbol| (range.index, symbol))
+    }
+
+    // Returns address ranges of the defined symbols sorted by the start
+    fn get_address_ranges(&self) -> Vec<AddressRange> {
+        let mut ranges: Vec<AddressRange> = (0..self.get_symbols_num())
+            .filter_map(|index| {
+                let symbol = self.get_symbol(index)?;
+                let is_defined = symbol.shndx != constant::SHN_UNDEF as ElfWord
+                    && symbol.stype != constant::STT_SECTION
+                    && symbol.stype != constant::STT_FILE;
+                if !is_defined || symbol.size == 0 {
+                    return None;
+                }
+
+                Some(AddressRange {
+                    start: symbol.value,
+                    end: symbol.value.saturating_add(symbol.size),
+                    max_end: 0,
+                    index,
+                })
+            })
+            .collect();
+
+        ranges.sort_by_key(|range| (range.start, range.index));
+        let mut max_end = 0;
+        for range in &mut ranges {
+            max_end = max_end.max(range.end);
+            range.max_end = max_end;
+        }
+
+        ranges
+    }
+
+    // Resolves SHN_XINDEX through the extended section index table linked
+    // to this symbol table. Returns the index and whether it is special.
+    // The table is looked up on the first call
+    fn get_section_index(&self, index: ElfXword, shndx: ElfHalf) -> (ElfWord, bool) {
+        if shndx != constant::SHN_XINDEX {
+            return (shndx as ElfWord, shndx >= constant::SHN_LORESERVE);
+        }
+
+        let extended = self.extended.get_or_init(|| {
+            let symtab_index = self.section.get_index();
+            self.elfio
+                .get_sections()
+                .iter()
+                .find(|section| {
+                    section.get_type() == constant::SHT_SYMTAB_SHNDX
+                        && section.get_link() == symtab_index
+                })
+                .map(|section| &**section)
+        });
+        extended
+            .and_then(|section| {
+                let offset = usize::try_from(index.checked_mul(4)?).ok()?;
+                let bytes = section.get_data().get(offset..offset.checked_add(4)?)?;
+                let converter = self.elfio.get_converter();
+                Some(converter.convert(u32::from_ne_bytes(<[u8; 4]>::try_from(bytes).ok()?)))
             })
+            .map_or((shndx as ElfWord, true), |shndx| (shndx, false))
+    }
+
+    // Resolves the symbol version through the symbol version table linked
+    // to this symbol table. Version indices 0 and 1 don't refer to versions.
+    // The version names are collected on the first call
+    fn get_symbol_version(&self, index: ElfXword) -> Option<SymbolVersion> {
+        if self.section.get_type() != constant::SHT_DYNSYM {
+            return None;
+        }
+
+        let versions = self.versions.get_or_init(|| self.get_versions());
+        let entry = VersymSectionAccessor::new(self.elfio, versions.versym?).get_entry(index)?;
+        let version_index = entry & constant::VERSYM_VERSION;
+        if version_index == constant::VER_NDX_LOCAL || version_index == constant::VER_NDX_GLOBAL {
+            return None;
+        }
+
+        Some(SymbolVersion {
+            name:   versions.names.get(&version_index)?.clone(),
+            hidden: entry & constant::VERSYM_HIDDEN != 0,
+        })
+    }
+
+    // Finds the symbol version table and collects the version names.
+    // The first name found for an index is used
+    fn get_versions(&self) -> Versions<'a> {
+        let sections = self.elfio.get_sections();
+        let symtab_index = self.section.get_index();
+        let versym = sections.iter().find(|section| {
+            section.get_type() == constant::SHT_GNU_VERSYM && section.get_link() == symtab_index
+        });
+
+        let mut names = HashMap::new();
+        for section in sections {
+            match section.get_type() {
+                constant::SHT_GNU_VERDEF => {
+                    let verdef = VerdefSectionAccessor::new(self.elfio, &**section);
+                    for definition in verdef.get_entries() {
+                        if let Some(name) = definition.names.into_iter().next() {
+                            names.entry(definition.index).or_insert(name);
+                        }
+                    }
+                }
+                constant::SHT_GNU_VERNEED => {
+                    let verneed = VerneedSectionAccessor::new(self.elfio, &**section);
+                    for requirement in verneed.get_entries() {
+                        for version in requirement.versions {
+                            names.entry(version.index).or_insert(version.name);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Versions {
+            versym: versym.map(|section| &**section),
+            names,
+        }
+    }
+
+    // Returns the symbol name from the linked string table
+    fn get_symbol_name(&self, offset: ElfWord) -> String {
+        match self
+            .elfio
+            .get_section_by_index(self.section.get_link())
+        {
+            Some(string_section) => {
+                StringSectionAccessor::new(self.elfio, string_section).get_string(offset)
+            }
+            None => String::new(),
+        }
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to modify symbol tables
+///
+/// Symbol names are stored in the string table referred by the section
+/// link. `sh_info` of the section, the index of the first non-local
+/// symbol, is maintained by the accessor.
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, Symbol, SymbolSectionAccessor, SymbolSectionAccessorMut};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let strtab = elf.add_section(".strtab");
+/// strtab.set_type(constant::SHT_STRTAB);
+/// let strtab_index = strtab.get_index();
+/// let symtab = elf.add_section(".symtab");
+/// symtab.set_type(constant::SHT_SYMTAB);
+/// symtab.set_link(strtab_index as u32);
+/// let symtab_index = symtab.get_index();
+///
+/// let mut symtab = SymbolSectionAccessorMut::new(&mut elf, symtab_index).unwrap();
+/// let main = Symbol {
+///     name: "main".to_string(),
+///     value: 0x401000,
+///     bind: constant::STB_GLOBAL,
+///     stype: constant::STT_FUNC,
+///     ..Default::default()
+/// };
+/// // The null symbol is added in front of the first symbol
+/// assert_eq!(symtab.add_symbol(&main, |_, _| ()), Some(1));
+///
+/// let section = elf.get_section_by_index(symtab_index).unwrap();
+/// let symtab = SymbolSectionAccessor::new(&elf, section);
+/// assert_eq!(symtab.get_symbols_num(), 2);
+/// assert_eq!(symtab.get_symbol(1).unwrap().name, "main");
+/// assert_eq!(section.get_info(), 1);
+/// ```
+pub struct SymbolSectionAccessorMut<'a, 'b> {
+    elfio:    &'a mut Elfio<'b>,
+    index:    ElfWord,
+    names:    StringIndex,
+    extended: OnceCell<Option<ElfWord>>,
+}
+
+// --------------------------------------------------------------------------
+impl<'a, 'b> SymbolSectionAccessorMut<'a, 'b> {
+    /// Creates a new instance of the symbol table accessor for the section
+    /// with the given index. Returns `None` if there is no such section or
+    /// the section entry size is set and differs from the size of symbol
+    /// table entries of the file class, or the data of the section
+    /// or of the section it is linked to fails to be read from a lazily
+    /// loaded file
+    pub fn new(
+        elfio: &'a mut Elfio<'b>,
+        index: ElfWord,
+    ) -> Option<SymbolSectionAccessorMut<'a, 'b>> {
+        elfio.read_section_data(index)?;
+        let entry_size = elfio.get_section_by_index(index)?.get_entry_size();
+        // Entries of other sizes can't be written without truncating them
+        let expected = if elfio.get_class() == constant::ELFCLASS64 {
+            std::mem::size_of::<Elf64Sym>()
+        } else {
+            std::mem::size_of::<Elf32Sym>()
+        };
+        if entry_size != 0 && entry_size != expected as ElfXword {
+            return None;
+        }
+
+        Some(SymbolSectionAccessorMut {
+            elfio,
+            index,
+            names: StringIndex::default(),
+            extended: OnceCell::new(),
+        })
+    }
+
+    /// Returns number of symbols
+    pub fn get_symbols_num(&self) -> ElfXword {
+        match self.elfio.get_section_by_index(self.index) {
+            Some(section) => SymbolSectionAccessor::new(self.elfio, section).get_symbols_num(),
+            None => 0,
+        }
+    }
+
+    /// Get a symbol by its index
+    pub fn get_symbol(&self, index: ElfXword) -> Option<Symbol> {
+        let section = self.elfio.get_section_by_index(self.index)?;
+        SymbolSectionAccessor::new(self.elfio, section).get_symbol(index)
+    }
+
+    /// Adds a symbol to the table and returns its index. The null symbol
+    /// is added first to an empty table. Non-local symbols are appended,
+    /// local ones are inserted in front of the non-local symbols, as ELF
+    /// requires. `func` is called with the old and the new indices of every
+    /// moved symbol, so that references to them, for example, by relocations
+    /// or by hash tables, can be updated. Returns `None` if the section link
+    /// doesn't refer to a string table to add the symbol name to
+    pub fn add_symbol<F>(&mut self, symbol: &Symbol, func: F) -> Option<ElfXword>
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        let entry = self.encode(symbol)?;
+        if self.get_symbols_num() == 0 {
+            let null = self.encode(&Symbol::default())?;
+            self.append_entry(&null, None);
+            self.set_info(1);
+        }
+
+        let mut index = self.append_entry(&entry, get_extended_index(symbol));
+        if symbol.bind == constant::STB_LOCAL {
+            let info = std::cmp::min(self.get_info(), index);
+            self.move_symbol(index, info, func);
+            self.set_info(info + 1);
+            index = info;
+        }
+
+        Some(index)
+    }
+
+    /// Replaces the symbol with the given index and returns its new index.
+    /// A symbol becoming local is moved to the end of the local symbols,
+    /// a symbol becoming non-local is moved to the beginning of
+    /// the non-local ones. `func` is called with the old and the new indices
+    /// of every moved symbol, including the replaced one. Returns `None` if
+    /// there is no such symbol or the symbol name can't be added to
+    /// the linked string table
+    pub fn update_symbol<F>(
+        &mut self,
+        index: ElfXword,
+        symbol: &Symbol,
+        mut func: F,
+    ) -> Option<ElfXword>
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        if index >= self.get_symbols_num() {
+            return None;
+        }
+
+        let entry = self.encode(symbol)?;
+        let section = self.get_section_mut();
+        let mut data = section.get_data().to_vec();
+        let offset = (index * section.get_entry_size()) as usize;
+        data.get_mut(offset..offset + entry.len())?.copy_from_slice(&entry);
+        section.set_data(&data);
+        self.set_extended_index(index, get_extended_index(symbol));
+
+        let info = self.get_info();
+        let new_index = if symbol.bind == constant::STB_LOCAL && index >= info {
+            self.set_info(info + 1);
+            info
+        } else if symbol.bind != constant::STB_LOCAL && index < info {
+            self.set_info(info - 1);
+            info - 1
+        } else {
+            return Some(index);
+        };
+        if new_index != index {
+            self.move_symbol(index, new_index, &mut func);
+            func(index, new_index);
+        }
+
+        Some(new_index)
+    }
+
+    /// Removes the symbol with the given index. The following symbols
+    /// are moved one position down. `func` is called with the old and
+    /// the new indices of every moved symbol, so that references to them,
+    /// for example, by relocations or by hash tables, can be updated.
+    /// Returns `false` if there is no such symbol
+    pub fn remove_symbol<F>(&mut self, index: ElfXword, mut func: F) -> bool
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        let num = self.get_symbols_num();
+        if index >= num {
+            return false;
+        }
+
+        let section = self.get_section_mut();
+        let mut data = section.get_data().to_vec();
+        let entry_size = section.get_entry_size() as usize;
+        let offset = index as usize * entry_size;
+        data.drain(offset..offset + entry_size);
+        section.set_data(&data);
+        if let Some(extended) = self.get_extended_table_mut() {
+            let mut data = extended.get_data().to_vec();
+            let offset = index as usize * 4;
+            if offset < data.len() {
+                data.drain(offset..std::cmp::min(offset + 4, data.len()));
+                extended.set_data(&data);
+            }
+        }
+
+        let info = self.get_info();
+        if index + 1 == info {
+            let info = self.find_info(index);
+            self.set_info(info);
+        } else if index < info {
+            self.set_info(info - 1);
         }
+
+        for old in index + 1..num {
+            func(old, old - 1);
+        }
+
+        true
+    }
+
+    /// Moves local symbols in front of non-local ones, as ELF requires.
+    /// `func` is called with the indices of every pair of swapped symbols,
+    /// so that references to them, for example, by relocations can be
+    /// updated
+    pub fn arrange_local_symbols<F>(&mut self, mut func: F)
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        let num = self.get_symbols_num();
+        let is_local: Vec<bool> = (0..num).map(|i| self.is_local(i)).collect();
+
+        let section = self.get_section_mut();
+        let entry_size = section.get_entry_size() as usize;
+        let mut data = section.get_data().to_vec();
+        let mut swapped = Vec::new();
+        let mut first = 1;
+        let mut last = num;
+        loop {
+            while first < last && is_local[first as usize] {
+                first += 1;
+            }
+            while first < last && !is_local[last as usize - 1] {
+                last -= 1;
+            }
+            if first + 1 >= last {
+                break;
+            }
+
+            last -= 1;
+            let (head, tail) = data.split_at_mut(last as usize * entry_size);
+            head[first as usize * entry_size..(first as usize + 1) * entry_size]
+                .swap_with_slice(&mut tail[..entry_size]);
+            func(first, last);
+            swapped.push((first as usize, last as usize));
+            first += 1;
+        }
+        section.set_data(&data);
+
+        if let Some(extended) = self.get_extended_table_mut() {
+            let mut data = extended.get_data().to_vec();
+            data.resize(std::cmp::max(data.len(), num as usize * 4), 0);
+            for (first, last) in swapped {
+                let (head, tail) = data.split_at_mut(last * 4);
+                head[first * 4..(first + 1) * 4].swap_with_slice(&mut tail[..4]);
+            }
+            extended.set_data(&data);
+        }
+        // The local symbols are placed in front of the others now
+        let locals = is_local.iter().filter(|local| **local).count();
+        self.set_info(locals as ElfXword);
+    }
+
+    // Moves the symbol to the new index, the symbols in between are moved
+    // one position towards the old one. `func` is called with the old and
+    // the new indices of every symbol in between
+    fn move_symbol<F>(&mut self, from: ElfXword, to: ElfXword, mut func: F)
+    where
+        F: FnMut(ElfXword, ElfXword),
+    {
+        if from == to {
+            return;
+        }
+
+        let num = self.get_symbols_num() as usize;
+        let (first, last) = (std::cmp::min(from, to) as usize, std::cmp::max(from, to) as usize);
+        let section = self.get_section_mut();
+        let entry_size = section.get_entry_size() as usize;
+        let mut data = section.get_data().to_vec();
+        rotate(&mut data[first * entry_size..(last + 1) * entry_size], entry_size, from < to);
+        section.set_data(&data);
+        if let Some(extended) = self.get_extended_table_mut() {
+            let mut data = extended.get_data().to_vec();
+            data.resize(std::cmp::max(data.len(), num * 4), 0);
+            rotate(&mut data[first * 4..(last + 1) * 4], 4, from < to);
+            extended.set_data(&data);
+        }
+
+        if from < to {
+            for old in from + 1..=to {
+                func(old, old - 1);
+            }
+        } else {
+            for old in (to..from).rev() {
+                func(old, old + 1);
+            }
+        }
+    }
+
+    // Appends an encoded entry to the section data and returns its index
+    fn append_entry(&mut self, entry: &[u8], extended: Option<ElfWord>) -> ElfXword {
+        let section = self.get_section_mut();
+        if section.get_entry_size() == 0 {
+            section.set_entry_size(entry.len() as ElfXword);
+        }
+        let index = section.get_data().len() as ElfXword / section.get_entry_size();
+        section.append_data(entry);
+        self.set_extended_index(index, extended);
+
+        index
+    }
+
+    // Stores the section index of the symbol in the extended section index
+    // table. The table is created when the index doesn't fit into st_shndx
+    fn set_extended_index(&mut self, index: ElfXword, extended: Option<ElfWord>) {
+        if self.get_extended_table_mut().is_none() {
+            if extended.is_none() {
+                return;
+            }
+
+            let symtab_index = self.index;
+            let extended = self.elfio.add_section(".symtab_shndx");
+            extended.set_type(constant::SHT_SYMTAB_SHNDX);
+            extended.set_link(symtab_index);
+            extended.set_entry_size(4);
+            extended.set_addr_align(4);
+            self.extended = OnceCell::from(Some(extended.get_index()));
+        }
+
+        let converter = *self.elfio.get_converter();
+        let value = extended.unwrap_or(0);
+        let extended = self.get_extended_table_mut().unwrap();
+        let mut data = extended.get_data().to_vec();
+        let offset = index as usize * 4;
+        data.resize(std::cmp::max(data.len(), offset + 4), 0);
+        data[offset..offset + 4].copy_from_slice(&converter.convert(value).to_ne_bytes());
+        extended.set_data(&data);
+    }
+
+    // Returns the extended section index table linked to the symbol table.
+    // The table is looked up on the first call
+    fn get_extended_table_mut(&mut self) -> Option<&mut dyn ElfSectionTrait> {
+        let index = *self.extended.get_or_init(|| {
+            self.elfio
+                .get_sections()
+                .iter()
+                .find(|section| {
+                    section.get_type() == constant::SHT_SYMTAB_SHNDX
+                        && section.get_link() == self.index
+                })
+                .map(|section| section.get_index())
+        });
+        self.elfio.get_section_by_index_mut(index?)
+    }
+
+    // Returns the index following the last local symbol preceding `end`
+    fn find_info(&self, end: ElfXword) -> ElfXword {
+        (0..end).rev().find(|i| self.is_local(*i)).map_or(0, |i| i + 1)
+    }
+
+    // Checks the symbol binding without decoding the whole entry
+    fn is_local(&self, index: ElfXword) -> bool {
+        // The section existence is checked on the accessor creation
+        let section = self.elfio.get_section_by_index(self.index).unwrap();
+        let info_offset = if self.elfio.get_class() == constant::ELFCLASS64 { 4 } else { 12 };
+        let offset = index * section.get_entry_size() + info_offset;
+        section
+            .get_data()
+            .get(offset as usize)
+            .is_none_or(|info| info >> 4 == constant::STB_LOCAL)
+    }
+
+    fn get_info(&self) -> ElfXword {
+        // The section existence is checked on the accessor creation
+        self.elfio.get_section_by_index(self.index).unwrap().get_info() as ElfXword
+    }
+
+    fn set_info(&mut self, info: ElfXword) {
+        self.get_section_mut().set_info(info as ElfWord);
+    }
+
+    // Converts the symbol to the symbol table entry. The name is added
+    // to the linked string table
+    fn encode(&mut self, symbol: &Symbol) -> Option<Vec<u8>> {
+        let converter = *self.elfio.get_converter();
+        let is_64 = self.elfio.get_class() == constant::ELFCLASS64;
+        let name = self.add_name(&symbol.name)?;
+        let info = (symbol.bind << 4) | (symbol.stype & 0xF);
+        // Section indices not fitting below the reserved range are stored
+        // in the extended section index table
+        let shndx = match get_extended_index(symbol) {
+            Some(_) => constant::SHN_XINDEX,
+            None => symbol.shndx as ElfHalf,
+        };
+
+        let mut entry = Vec::new();
+        if is_64 {
+            entry.extend_from_slice(&converter.convert(name).to_ne_bytes());
+            entry.push(info);
+            entry.push(symbol.other);
+            entry.extend_from_slice(&converter.convert(shndx).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(symbol.value).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(symbol.size).to_ne_bytes());
+        } else {
+            entry.extend_from_slice(&converter.convert(name).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(symbol.value as u32).to_ne_bytes());
+            entry.extend_from_slice(&converter.convert(symbol.size as u32).to_ne_bytes());
+            entry.push(info);
+            entry.push(symbol.other);
+            entry.extend_from_slice(&converter.convert(shndx).to_ne_bytes());
+        }
+
+        Some(entry)
+    }
+
+    // Adds the symbol name to the linked string table and returns its index.
+    // Returns `None` if the section link doesn't refer to a string table
+    fn add_name(&mut self, name: &str) -> Option<ElfWord> {
+        if name.is_empty() {
+            return Some(0);
+        }
+
+        let link = self.get_section_mut().get_link();
+        let strtab = self.elfio.get_section_by_index_mut(link)?;
+        if link == self.index || strtab.get_type() != constant::SHT_STRTAB {
+            return None;
+        }
+
+        Some(self.names.find_or_add_string(strtab, name))
+    }
+
+    // The section existence is checked on the accessor creation
+    fn get_section_mut(&mut self) -> &mut dyn ElfSectionTrait {
+        self.elfio.get_section_by_index_mut(self.index).unwrap()
+    }
+}
+
+// --------------------------------------------------------------------------
+// Returns the section index to be stored in the extended section index table
+// for the symbol. Special indices are stored in st_shndx as is
+fn get_extended_index(symbol: &Symbol) -> Option<ElfWord> {
+    if !symbol.special_shndx && symbol.shndx >= constant::SHN_LORESERVE as ElfWord {
+        Some(symbol.shndx)
+    } else {
+        None
+    }
+}
+
+// Moves the first entry of the data to its end, or the last one to its
+// beginning
+fn rotate(data: &mut [u8], entry_size: usize, to_end: bool) {
+    if to_end {
+        data.rotate_left(entry_size);
+    } else {
+        data.rotate_right(entry_size);
     }
 }
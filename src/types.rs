@@ -112,6 +112,10 @@ pub mod constant {
     /// Core file
     pub const ET_CORE: ElfHalf = 4;
 
+    /// The number of program headers doesn't fit into e_phnum. The real
+    /// number is kept in sh_info of the section 0
+    pub const PN_XNUM: ElfHalf = 0xFFFF;
+
     // Section indexes
     /// This value marks an undefined, missing, irrelevant, or otherwise
     /// meaningless section reference.
@@ -173,6 +177,14 @@ pub mod constant {
     pub const SHT_GROUP: ElfWord = 17;
     /// The section header index of the associated symbol table section
     pub const SHT_SYMTAB_SHNDX: ElfWord = 18;
+    /// The section holds a GNU-style symbol hash table
+    pub const SHT_GNU_HASH: ElfWord = 0x6ffffff6;
+    /// The section holds GNU symbol version definitions
+    pub const SHT_GNU_VERDEF: ElfWord = 0x6ffffffd;
+    /// The section holds GNU symbol version requirements
+    pub const SHT_GNU_VERNEED: ElfWord = 0x6ffffffe;
+    /// The section holds GNU symbol version indices
+    pub const SHT_GNU_VERSYM: ElfWord = 0x6fffffff;
     /// Values in this inclusive range are reserved for operating system-specific semantics
     pub const SHT_LOOS: ElfWord = 0x60000000;
     /// Values in this inclusive range are reserved for operating system-specific semantics
@@ -238,6 +250,12 @@ pub mod constant {
     pub const PT_TLS: ElfWord = 7;
     /// Values in this inclusive range are reserved for operating system-specific semantics
     pub const PT_LOOS: ElfWord = 0x60000000;
+    /// The array element specifies the location and size of the exception handling information
+    pub const PT_GNU_EH_FRAME: ElfWord = 0x6474e550;
+    /// The array element specifies the stack executability
+    pub const PT_GNU_STACK: ElfWord = 0x6474e551;
+    /// The array element specifies the segment part made read-only after relocation
+    pub const PT_GNU_RELRO: ElfWord = 0x6474e552;
     /// Values in this inclusive range are reserved for operating system-specific semantics
     pub const PT_HIOS: ElfWord = 0x6fffffff;
     /// Values in this inclusive range are reserved for processor-specific semantics
@@ -286,7 +304,7 @@ pub mod constant {
     pub const STT_FILE: u8 = 4;
     /// The symbol labels a common block that has not yet been allocated.
     pub const STT_COMMON: u8 = 5;
-    ///
+    /// The symbol specifies a Thread-Local Storage entity
     pub const STT_TLS: u8 = 6;
     /// Environment-specific use
     pub const STT_LOOS: u8 = 10;
@@ -297,6 +315,20 @@ pub mod constant {
     /// Values in this inclusive range are reserved for processor-specific semantics
     pub const STT_HIPROC: u8 = 15;
 
+    // Symbol versioning
+    /// The symbol is local, not available outside the object
+    pub const VER_NDX_LOCAL: ElfHalf = 0;
+    /// The symbol is defined in this object and globally available
+    pub const VER_NDX_GLOBAL: ElfHalf = 1;
+    /// The symbol version can't be used by default
+    pub const VERSYM_HIDDEN: ElfHalf = 0x8000;
+    /// The mask of the version index in symbol version entries
+    pub const VERSYM_VERSION: ElfHalf = 0x7fff;
+    /// The version definition of the file itself
+    pub const VER_FLG_BASE: ElfHalf = 1;
+    /// A weak version reference
+    pub const VER_FLG_WEAK: ElfHalf = 2;
+
     // Dynamic Array Tags
     /// Marks the end of the dynamic array
     pub const DT_NULL: ElfSxword = 0;
@@ -364,26 +396,62 @@ pub mod constant {
     pub const DT_INIT_ARRAYSZ: ElfSxword = 27;
     /// Size, in bytes, of the array of termination functions
     pub const DT_FINI_ARRAYSZ: ElfSxword = 28;
-    ///
+    /// The string table offset of a library search path string
     pub const DT_RUNPATH: ElfSxword = 29;
-    ///
+    /// Flag values specific to the object being loaded
     pub const DT_FLAGS: ElfSxword = 30;
-    ///
+    /// Dynamic tags at or above this value follow the d_un encoding rules
     pub const DT_ENCODING: ElfSxword = 32;
-    ///
+    /// Pointer to an array of pointers to pre-initialization functions
     pub const DT_PREINIT_ARRAY: ElfSxword = 32;
-    ///
+    /// Size, in bytes, of the array of pre-initialization functions
     pub const DT_PREINIT_ARRAYSZ: ElfSxword = 33;
-    ///
+    /// Number of positive dynamic tags
     pub const DT_MAXPOSTAGS: ElfSxword = 34;
+    /// Address of the GNU-style symbol hash table
+    pub const DT_GNU_HASH: ElfSxword = 0x6ffffef5;
+    /// Address of the symbol version index table
+    pub const DT_VERSYM: ElfSxword = 0x6ffffff0;
+    /// State flags of the object, the GNU extension
+    pub const DT_FLAGS_1: ElfSxword = 0x6ffffffb;
+    /// Address of the version definition table
+    pub const DT_VERDEF: ElfSxword = 0x6ffffffc;
+    /// Number of version definitions
+    pub const DT_VERDEFNUM: ElfSxword = 0x6ffffffd;
+    /// Address of the version requirement table
+    pub const DT_VERNEED: ElfSxword = 0x6ffffffe;
+    /// Number of version requirements
+    pub const DT_VERNEEDNUM: ElfSxword = 0x6fffffff;
     /// Defines a range of dynamic table tags that are reserved for
     /// environment-specific use
     pub const DT_LOOS: ElfSxword = 0x6000000D;
-    ///
+    /// Upper bound of the environment-specific range
     pub const DT_HIOS: ElfSxword = 0x6ffff000;
     /// Defines a range of dynamic table tags that are reserved for
     /// processor-specific use
     pub const DT_LOPROC: ElfSxword = 0x70000000;
-    ///
+    /// Upper bound of the processor-specific range
     pub const DT_HIPROC: ElfSxword = 0x7FFFFFFF;
+
+    // Auxiliary vector types
+    /// Marks the end of the auxiliary vector
+    pub const AT_NULL: ElfXword = 0;
+    /// The entry should be ignored
+    pub const AT_IGNORE: ElfXword = 1;
+    /// File descriptor of the program
+    pub const AT_EXECFD: ElfXword = 2;
+    /// Address of the program headers of the program
+    pub const AT_PHDR: ElfXword = 3;
+    /// Size of a program header entry
+    pub const AT_PHENT: ElfXword = 4;
+    /// Number of the program headers
+    pub const AT_PHNUM: ElfXword = 5;
+    /// System page size
+    pub const AT_PAGESZ: ElfXword = 6;
+    /// Base address of the interpreter
+    pub const AT_BASE: ElfXword = 7;
+    /// Flags
+    pub const AT_FLAGS: ElfXword = 8;
+    /// Entry point of the program
+    pub const AT_ENTRY: ElfXword = 9;
 }
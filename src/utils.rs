@@ -21,8 +21,14 @@ THE SOFTWARE.
 */
 
 use num_traits::AsPrimitive;
+use std::cell::{OnceCell, RefCell};
+use std::fmt;
 use std::io;
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
+use std::ops::Range;
+use std::rc::Rc;
+
+use super::types::*;
 
 // --------------------------------------------------------------------------
 /// A trait for reading ELF file payload from a file or memory.
@@ -31,19 +37,37 @@ use std::io::{Read, Seek};
 pub trait ElfioReadSeek: Read + Seek {}
 impl ElfioReadSeek for std::fs::File {}
 impl<T: Read + Seek> ElfioReadSeek for std::io::BufReader<T> {}
-impl<T: Read + Seek + AsRef<[u8]>> ElfioReadSeek for std::io::Cursor<T> {}
+impl<T: AsRef<[u8]>> ElfioReadSeek for std::io::Cursor<T> {}
+
+// --------------------------------------------------------------------------
+/// A trait for writing ELF file payload to a file or memory.
+/// Currently, it is implemented for std::fs::File, std::io::BufWriter
+/// and std::io::Cursor
+pub trait ElfioWriteSeek: Write + Seek {}
+impl ElfioWriteSeek for std::fs::File {}
+impl<T: Write + Seek> ElfioWriteSeek for std::io::BufWriter<T> {}
+impl ElfioWriteSeek for std::io::Cursor<Vec<u8>> {}
+impl ElfioWriteSeek for std::io::Cursor<&mut Vec<u8>> {}
+impl ElfioWriteSeek for std::io::Cursor<&mut [u8]> {}
+impl ElfioWriteSeek for std::io::Cursor<Box<[u8]>> {}
 
 // --------------------------------------------------------------------------
 /// The trait for (de)serializing ELF entities
 pub trait Load {
-    fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()>;
+    fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()>;
+}
+
+// --------------------------------------------------------------------------
+/// The trait for serializing ELF entities
+pub trait Save {
+    fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()>;
 }
 
 // --------------------------------------------------------------------------
 macro_rules! impl_load_for {
     ( $x:ty ) => {
         impl Load for $x {
-            fn load(&mut self, reader: &mut (dyn ElfioReadSeek)) -> io::Result<()> {
+            fn load(&mut self, reader: &mut dyn ElfioReadSeek) -> io::Result<()> {
                 let mut buffer = self.to_ne_bytes();
                 reader.read_exact(&mut buffer)?;
                 *self = <$x>::from_ne_bytes(buffer);
@@ -68,6 +92,172 @@ impl Load for &mut [u8; 16] {
     }
 }
 
+// --------------------------------------------------------------------------
+macro_rules! impl_save_for {
+    ( $x:ty ) => {
+        impl Save for $x {
+            fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+                writer.write_all(&self.to_ne_bytes())?;
+
+                Ok(())
+            }
+        }
+    };
+}
+
+// --------------------------------------------------------------------------
+impl_save_for!(u8);
+impl_save_for!(u16);
+impl_save_for!(u32);
+impl_save_for!(u64);
+
+impl Save for [u8; 16] {
+    fn save(&self, writer: &mut dyn ElfioWriteSeek) -> io::Result<()> {
+        writer.write_all(self)?;
+
+        Ok(())
+    }
+}
+
+// --------------------------------------------------------------------------
+// Location of the section or segment data which is not read from the file yet
+pub(crate) struct DataSource {
+    reader: Rc<RefCell<dyn ElfioReadSeek>>,
+    offset: Elf64Off,
+    size:   ElfXword,
+}
+
+impl fmt::Debug for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataSource")
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+// --------------------------------------------------------------------------
+// Section and segment data storage
+#[derive(Debug)]
+pub(crate) enum DataStorage<'a> {
+    // Data owned by the section or segment
+    Owned(Vec<u8>),
+    // Data read from the source on the first access
+    Lazy(DataSource, OnceCell<Vec<u8>>),
+    // Data borrowed from the ELF file image
+    Borrowed(&'a [u8]),
+    // Part of the file read once and shared by the sections and segments
+    Shared(Rc<Vec<u8>>, Range<usize>),
+}
+
+impl<'a> DataStorage<'a> {
+    // The data is borrowed from the ELF file image, if it is provided,
+    // instead of being copied
+    pub(crate) fn load(
+        reader: &mut dyn ElfioReadSeek,
+        image: Option<&'a [u8]>,
+        offset: Elf64Off,
+        size: ElfXword,
+    ) -> io::Result<DataStorage<'a>> {
+        if let Some(image) = image {
+            let start = offset as usize;
+            let end = start.saturating_add(size as usize);
+            let data = image
+                .get(start..end)
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+            Ok(DataStorage::Borrowed(data))
+        } else {
+            let mut data = vec![0; size as usize];
+            reader.seek(io::SeekFrom::Start(offset))?;
+            reader.read_exact(&mut data)?;
+            Ok(DataStorage::Owned(data))
+        }
+    }
+
+    // The data refers to the part of the file content covered by
+    // the section or segment. Segments usually cover the sections,
+    // so the same bytes are not kept in memory twice
+    pub(crate) fn shared(
+        content: &Rc<Vec<u8>>,
+        offset: Elf64Off,
+        size: ElfXword,
+    ) -> io::Result<DataStorage<'a>> {
+        let start = offset as usize;
+        let end = start.saturating_add(size as usize);
+        if end > content.len() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+
+        Ok(DataStorage::Shared(Rc::clone(content), start..end))
+    }
+
+    pub(crate) fn lazy(
+        reader: &Rc<RefCell<dyn ElfioReadSeek>>,
+        offset: Elf64Off,
+        size: ElfXword,
+    ) -> DataStorage<'a> {
+        let source = DataSource {
+            reader: Rc::clone(reader),
+            offset,
+            size,
+        };
+        DataStorage::Lazy(source, OnceCell::new())
+    }
+
+    pub(crate) fn get(&self) -> &[u8] {
+        self.try_get().unwrap_or(&[])
+    }
+
+    // Read errors are not cached, the next call tries again
+    pub(crate) fn try_get(&self) -> io::Result<&[u8]> {
+        match self {
+            DataStorage::Owned(data) => Ok(data),
+            DataStorage::Borrowed(data) => Ok(data),
+            DataStorage::Shared(content, range) => Ok(&content[range.clone()]),
+            DataStorage::Lazy(source, cache) => {
+                if let Some(data) = cache.get() {
+                    return Ok(data);
+                }
+
+                let data = Self::read(source)?;
+                Ok(cache.get_or_init(|| data))
+            }
+        }
+    }
+
+    // The data is made owned to be modified in place. It is copied from
+    // the file image or read from the data source on the first call only
+    pub(crate) fn get_mut(&mut self) -> io::Result<&mut [u8]> {
+        let data = match self {
+            DataStorage::Owned(_) => None,
+            DataStorage::Borrowed(data) => Some(data.to_vec()),
+            DataStorage::Shared(content, range) => Some(content[range.clone()].to_vec()),
+            DataStorage::Lazy(source, cache) => match cache.take() {
+                Some(data) => Some(data),
+                None => Some(Self::read(source)?),
+            },
+        };
+        if let Some(data) = data {
+            *self = DataStorage::Owned(data);
+        }
+
+        match self {
+            DataStorage::Owned(data) => Ok(data),
+            _ => unreachable!("the data is made owned above"),
+        }
+    }
+
+    // Reads the data from the data source
+    fn read(source: &DataSource) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; source.size as usize];
+        let mut reader = source.reader.borrow_mut();
+        reader.seek(io::SeekFrom::Start(source.offset))?;
+        reader.read_exact(&mut data)?;
+
+        Ok(data)
+    }
+}
+
 // --------------------------------------------------------------------------
 pub trait Convert<T>
 where
@@ -120,7 +310,7 @@ impl_convert_for!(i64);
 
 // --------------------------------------------------------------------------
 #[test]
-fn test_conv() -> () {
+fn test_conv() {
     let conv = Converter { is_needed: true };
 
     let a = 0x12u8;
@@ -140,7 +330,7 @@ fn test_conv() -> () {
 
 // --------------------------------------------------------------------------
 #[test]
-fn test_no_conv() -> () {
+fn test_no_conv() {
     let conv = Converter { is_needed: false };
 
     let a = 0x12u8;
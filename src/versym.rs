@@ -0,0 +1,409 @@
+/*
+Copyright (C) 2021-present by Serge Lamikhov-Center
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in
+all copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
+THE SOFTWARE.
+*/
+
+use std::convert::TryFrom;
+
+use super::elfio::*;
+use super::section::*;
+use super::strings::*;
+use super::types::*;
+use super::utils::{Convert, Converter};
+
+/// A symbol version definition
+///
+/// See documentation for [VerdefSectionAccessor] for usage example
+#[derive(Debug, Default, Clone)]
+pub struct VersionDefinition {
+    /// The version of the structure
+    pub version: ElfHalf,
+    /// Version information flags, for example, `VER_FLG_BASE`
+    pub flags:   ElfHalf,
+    /// The version index referred by the symbol version table
+    pub index:   ElfHalf,
+    /// The hash value of the version name
+    pub hash:    ElfWord,
+    /// The version name followed by the names of its predecessors
+    pub names:   Vec<String>,
+}
+
+/// A file the symbol versions are required from
+///
+/// See documentation for [VerneedSectionAccessor] for usage example
+#[derive(Debug, Default, Clone)]
+pub struct VersionRequirement {
+    /// The version of the structure
+    pub version:  ElfHalf,
+    /// The name of the file providing the versions
+    pub file:     String,
+    /// The versions required from the file
+    pub versions: Vec<VersionNeed>,
+}
+
+/// A symbol version required from a file
+///
+/// See documentation for [VerneedSectionAccessor] for usage example
+#[derive(Debug, Default, Clone)]
+pub struct VersionNeed {
+    /// The hash value of the version name
+    pub hash:  ElfWord,
+    /// Version information flags, for example, `VER_FLG_WEAK`
+    pub flags: ElfHalf,
+    /// The version index referred by the symbol version table
+    pub index: ElfHalf,
+    /// The version name
+    pub name:  String,
+}
+
+// Sizes of Elf_Verdef, Elf_Verdaux, Elf_Verneed and Elf_Vernaux structures.
+// They are the same for both ELF classes
+const VERDEF_SIZE: usize = 20;
+const VERDAUX_SIZE: usize = 8;
+const VERNEED_SIZE: usize = 16;
+const VERNAUX_SIZE: usize = 16;
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to symbol version tables
+/// (`.gnu.version`). The table holds a version index for every symbol of
+/// the linked dynamic symbol table
+///
+/// For example:
+/// ```
+/// use std::fs::File;
+/// use std::io;
+/// use std::io::{BufReader, Error};
+///
+/// use elfio::{constant, Elfio, VersymSectionAccessor};
+///
+/// fn main() -> io::Result<()> {
+///     let elf_file = File::open("tests/files/hello_64")?;
+///     let mut reader = BufReader::new(elf_file);
+///
+///     let mut elf = Elfio::new();
+///
+///     elf.load(&mut reader)?;
+///
+///     let section = match elf.get_section_by_name(".gnu.version") {
+///         Some(s) => s,
+///         None => return Err(Error::new(io::ErrorKind::Other, "section not found")),
+///     };
+///
+///     let versym = VersymSectionAccessor::new(&elf, section);
+///     assert_eq!(versym.get_entries_num(), 4);
+///     assert_eq!(versym.get_entry(1), Some(constant::VER_NDX_LOCAL));
+///     assert_eq!(versym.get_entry(2), Some(2));
+///
+///     Ok(())
+/// }
+/// ```
+pub struct VersymSectionAccessor<'a> {
+    elfio:   &'a Elfio<'a>,
+    section: &'a dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> VersymSectionAccessor<'a> {
+    /// Creates a new instance of the symbol version table accessor
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> VersymSectionAccessor<'a> {
+        VersymSectionAccessor { elfio, section }
+    }
+
+    /// Returns number of entries
+    pub fn get_entries_num(&self) -> ElfXword {
+        self.section.get_data().len() as ElfXword / 2
+    }
+
+    /// Returns the version entry of the symbol with the given index. The
+    /// version index is masked by `VERSYM_VERSION`, `VERSYM_HIDDEN` marks
+    /// a hidden symbol version
+    pub fn get_entry(&self, index: ElfXword) -> Option<ElfHalf> {
+        let offset = usize::try_from(index.checked_mul(2)?).ok()?;
+        get_half(self.section.get_data(), self.elfio.get_converter(), offset)
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to symbol version definitions
+/// (`.gnu.version_d`). Version names are taken from the linked string
+/// table
+///
+/// For example:
+/// ```
+/// use elfio::{constant, Elfio, VerdefSectionAccessor};
+///
+/// let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+/// let dynstr = elf.add_section(".dynstr");
+/// dynstr.set_type(constant::SHT_STRTAB);
+/// dynstr.set_data(b"\0libfoo.so\0FOO_1.0\0");
+/// let verdef = elf.add_section(".gnu.version_d");
+/// verdef.set_type(constant::SHT_GNU_VERDEF);
+/// verdef.set_link(1);
+/// verdef.set_info(1);
+/// verdef.set_data(&[
+///     1, 0, 0, 0, 2, 0, 1, 0, 0, 0, 0, 0, 20, 0, 0, 0, 0, 0, 0, 0, // Elf_Verdef
+///     11, 0, 0, 0, 0, 0, 0, 0, // Elf_Verdaux
+/// ]);
+///
+/// let verdef = VerdefSectionAccessor::new(&elf, elf.get_section_by_index(2).unwrap());
+/// let definitions = verdef.get_entries();
+/// assert_eq!(definitions.len(), 1);
+/// assert_eq!(definitions[0].index, 2);
+/// assert_eq!(definitions[0].names, ["FOO_1.0"]);
+/// ```
+pub struct VerdefSectionAccessor<'a> {
+    elfio:   &'a Elfio<'a>,
+    section: &'a dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> VerdefSectionAccessor<'a> {
+    /// Creates a new instance of the version definition accessor
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> VerdefSectionAccessor<'a> {
+        VerdefSectionAccessor { elfio, section }
+    }
+
+    /// Returns number of version definitions. The number is stored in
+    /// `sh_info` of the section
+    pub fn get_entries_num(&self) -> ElfXword {
+        self.section.get_info() as ElfXword
+    }
+
+    /// Walks the version definition chain and returns all definitions.
+    /// The walk stops at the first malformed entry. Overlapping entries
+    /// are visited no more times than the section data could hold
+    /// distinct ones
+    pub fn get_entries(&self) -> Vec<VersionDefinition> {
+        let data = self.section.get_data();
+        let converter = self.elfio.get_converter();
+        let strings = get_strings(self.elfio, self.section);
+
+        let mut entries = Vec::new();
+        let mut offset = 0usize;
+        let num = std::cmp::min(self.get_entries_num(), (data.len() / VERDEF_SIZE) as ElfXword);
+        let mut aux_left = data.len() / VERDAUX_SIZE;
+        while (entries.len() as ElfXword) < num {
+            let verdef = match data.get(offset..offset.saturating_add(VERDEF_SIZE)) {
+                Some(verdef) => verdef,
+                None => break,
+            };
+            let half = |pos| get_half(verdef, converter, pos).unwrap_or(0);
+            let word = |pos| get_word(verdef, converter, pos).unwrap_or(0);
+
+            let mut names = Vec::new();
+            let mut aux_offset = offset.saturating_add(word(12) as usize);
+            for _ in 0..std::cmp::min(half(6) as usize, aux_left) {
+                aux_left -= 1;
+                let verdaux = match data.get(aux_offset..aux_offset.saturating_add(VERDAUX_SIZE)) {
+                    Some(verdaux) => verdaux,
+                    None => break,
+                };
+                let name = get_word(verdaux, converter, 0).unwrap_or(0);
+                names.push(strings.as_ref().map_or_else(String::new, |s| s.get_string(name)));
+
+                let next = get_word(verdaux, converter, 4).unwrap_or(0);
+                if next == 0 {
+                    break;
+                }
+                aux_offset = aux_offset.saturating_add(next as usize);
+            }
+
+            entries.push(VersionDefinition {
+                version: half(0),
+                flags: half(2),
+                index: half(4),
+                hash: word(8),
+                names,
+            });
+
+            let next = word(16);
+            if next == 0 {
+                break;
+            }
+            offset = offset.saturating_add(next as usize);
+        }
+
+        entries
+    }
+
+    /// Returns the version definition with the given version index
+    pub fn find_by_index(&self, index: ElfHalf) -> Option<VersionDefinition> {
+        self.get_entries()
+            .into_iter()
+            .find(|definition| definition.index == index)
+    }
+}
+
+// --------------------------------------------------------------------------
+/// A section data accessor intended to symbol version requirements
+/// (`.gnu.version_r`). File and version names are taken from the linked
+/// string table
+///
+/// For example:
+/// ```
+/// use std::fs::File;
+/// use std::io;
+/// use std::io::{BufReader, Error};
+///
+/// use elfio::{Elfio, VerneedSectionAccessor};
+///
+/// fn main() -> io::Result<()> {
+///     let elf_file = File::open("tests/files/hello_ppc")?;
+///     let mut reader = BufReader::new(elf_file);
+///
+///     let mut elf = Elfio::new();
+///
+///     elf.load(&mut reader)?;
+///
+///     let section = match elf.get_section_by_name(".gnu.version_r") {
+///         Some(s) => s,
+///         None => return Err(Error::new(io::ErrorKind::Other, "section not found")),
+///     };
+///
+///     let verneed = VerneedSectionAccessor::new(&elf, section);
+///     let requirements = verneed.get_entries();
+///     assert_eq!(requirements.len(), 2);
+///     assert_eq!(requirements[0].file, "libstdc++.so.6");
+///     assert_eq!(requirements[0].versions[0].name, "CXXABI_1.3");
+///     assert_eq!(requirements[0].versions[0].index, 5);
+///     assert_eq!(requirements[1].file, "libc.so.6");
+///
+///     Ok(())
+/// }
+/// ```
+pub struct VerneedSectionAccessor<'a> {
+    elfio:   &'a Elfio<'a>,
+    section: &'a dyn ElfSectionTrait,
+}
+
+// --------------------------------------------------------------------------
+impl<'a> VerneedSectionAccessor<'a> {
+    /// Creates a new instance of the version requirement accessor
+    pub fn new(
+        elfio: &'a Elfio<'a>,
+        section: &'a dyn ElfSectionTrait,
+    ) -> VerneedSectionAccessor<'a> {
+        VerneedSectionAccessor { elfio, section }
+    }
+
+    /// Returns number of files the versions are required from. The number
+    /// is stored in `sh_info` of the section
+    pub fn get_entries_num(&self) -> ElfXword {
+        self.section.get_info() as ElfXword
+    }
+
+    /// Walks the version requirement chain and returns all requirements.
+    /// The walk stops at the first malformed entry. Overlapping entries
+    /// are visited no more times than the section data could hold
+    /// distinct ones
+    pub fn get_entries(&self) -> Vec<VersionRequirement> {
+        let data = self.section.get_data();
+        let converter = self.elfio.get_converter();
+        let strings = get_strings(self.elfio, self.section);
+        let get_string =
+            |index: ElfWord| strings.as_ref().map_or_else(String::new, |s| s.get_string(index));
+
+        let mut entries = Vec::new();
+        let mut offset = 0usize;
+        let num = std::cmp::min(self.get_entries_num(), (data.len() / VERNEED_SIZE) as ElfXword);
+        let mut aux_left = data.len() / VERNAUX_SIZE;
+        while (entries.len() as ElfXword) < num {
+            let verneed = match data.get(offset..offset.saturating_add(VERNEED_SIZE)) {
+                Some(verneed) => verneed,
+                None => break,
+            };
+            let half = |pos| get_half(verneed, converter, pos).unwrap_or(0);
+            let word = |pos| get_word(verneed, converter, pos).unwrap_or(0);
+
+            let mut versions = Vec::new();
+            let mut aux_offset = offset.saturating_add(word(8) as usize);
+            for _ in 0..std::cmp::min(half(2) as usize, aux_left) {
+                aux_left -= 1;
+                let vernaux = match data.get(aux_offset..aux_offset.saturating_add(VERNAUX_SIZE)) {
+                    Some(vernaux) => vernaux,
+                    None => break,
+                };
+                let half = |pos| get_half(vernaux, converter, pos).unwrap_or(0);
+                let word = |pos| get_word(vernaux, converter, pos).unwrap_or(0);
+                versions.push(VersionNeed {
+                    hash:  word(0),
+                    flags: half(4),
+                    index: half(6),
+                    name:  get_string(word(8)),
+                });
+
+                if word(12) == 0 {
+                    break;
+                }
+                aux_offset = aux_offset.saturating_add(word(12) as usize);
+            }
+
+            entries.push(VersionRequirement {
+                version: half(0),
+                file: get_string(word(4)),
+                versions,
+            });
+
+            let next = word(12);
+            if next == 0 {
+                break;
+            }
+            offset = offset.saturating_add(next as usize);
+        }
+
+        entries
+    }
+
+    /// Returns the required version with the given version index
+    pub fn find_by_index(&self, index: ElfHalf) -> Option<VersionNeed> {
+        self.get_entries()
+            .into_iter()
+            .flat_map(|requirement| requirement.versions)
+            .find(|version| version.index == index)
+    }
+}
+
+// --------------------------------------------------------------------------
+// Returns the accessor of the string table linked to the section
+fn get_strings<'a>(
+    elfio: &'a Elfio<'a>,
+    section: &dyn ElfSectionTrait,
+) -> Option<StringSectionAccessor<'a>> {
+    let strtab = elfio.get_section_by_index(section.get_link())?;
+    Some(StringSectionAccessor::new(elfio, strtab))
+}
+
+// --------------------------------------------------------------------------
+fn get_half(data: &[u8], converter: &Converter, offset: usize) -> Option<ElfHalf> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(converter.convert(u16::from_ne_bytes(<[u8; 2]>::try_from(bytes).ok()?)))
+}
+
+// --------------------------------------------------------------------------
+fn get_word(data: &[u8], converter: &Converter, offset: usize) -> Option<ElfWord> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(converter.convert(u32::from_ne_bytes(<[u8; 4]>::try_from(bytes).ok()?)))
+}
@@ -20,11 +20,13 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 THE SOFTWARE.
 */
 
+#![allow(clippy::get_first)]
+
 use std::fs::File;
 use std::io;
 use std::io::BufReader;
 
-use elfio::Elfio;
+use elfio::{Elfio, ElfioError};
 
 #[test]
 fn read_le_32() -> io::Result<()> {
@@ -443,3 +445,952 @@ fn read_be_ppc64() -> io::Result<()> {
 
     Ok(())
 }
+
+fn save_and_compare(file_name: &str) -> io::Result<()> {
+    let original = std::fs::read(file_name)?;
+
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(&original[..]))?;
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+
+    assert_eq!(writer.get_ref().len(), original.len());
+    assert!(writer.get_ref() == &original);
+
+    Ok(())
+}
+
+#[test]
+fn save_le_32() -> io::Result<()> {
+    save_and_compare("tests/files/hello_32")
+}
+
+#[test]
+fn save_le_64() -> io::Result<()> {
+    save_and_compare("tests/files/hello_64")
+}
+
+#[test]
+fn save_be_32() -> io::Result<()> {
+    save_and_compare("tests/files/hello_ppc")
+}
+
+#[test]
+fn save_be_64() -> io::Result<()> {
+    save_and_compare("tests/files/hello_ppc64")
+}
+
+#[test]
+fn save_header_only() -> io::Result<()> {
+    for class in [elfio::constant::ELFCLASS32, elfio::constant::ELFCLASS64] {
+        for encoding in [elfio::constant::ELFDATA2LSB, elfio::constant::ELFDATA2MSB] {
+            let mut elf = Elfio::new_(class, encoding);
+            elf.set_os_abi(3);
+            elf.set_type(elfio::constant::ET_EXEC);
+            elf.set_machine(0x28);
+            elf.set_flags(0x05000000);
+            elf.set_entry(0x8000);
+
+            let mut writer = io::Cursor::new(Vec::new());
+            elf.save(&mut writer)?;
+
+            let mut reader = io::Cursor::new(writer.into_inner());
+            let mut loaded = Elfio::new();
+            loaded.load(&mut reader)?;
+
+            assert_eq!(loaded.get_class(), class);
+            assert_eq!(loaded.get_encoding(), encoding);
+            assert_eq!(loaded.get_elf_version(), 1);
+            assert_eq!(loaded.get_version(), 1);
+            assert_eq!(loaded.get_os_abi(), 3);
+            assert_eq!(loaded.get_type(), elfio::constant::ET_EXEC);
+            assert_eq!(loaded.get_machine(), 0x28);
+            assert_eq!(loaded.get_flags(), 0x05000000);
+            assert_eq!(loaded.get_entry(), 0x8000);
+            assert_eq!(loaded.get_sections_num(), 0);
+            assert_eq!(loaded.get_segments_num(), 0);
+            if class == elfio::constant::ELFCLASS64 {
+                assert_eq!(loaded.get_header_size(), 64);
+                assert_eq!(loaded.get_segment_entry_size(), 56);
+                assert_eq!(loaded.get_section_entry_size(), 64);
+            } else {
+                assert_eq!(loaded.get_header_size(), 52);
+                assert_eq!(loaded.get_segment_entry_size(), 32);
+                assert_eq!(loaded.get_section_entry_size(), 40);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn get_segment_sections(elf: &Elfio, index: usize) -> String {
+    elf.get_segments()[index]
+        .get_section_indices()
+        .iter()
+        .map(|index| elf.get_section_by_index(*index).unwrap().get_name())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[test]
+fn segment_to_section_mapping() -> io::Result<()> {
+    // The mapping is the same as shown by `readelf -l`
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_64")?))?;
+    assert_eq!(get_segment_sections(&elf, 0), "");
+    assert_eq!(get_segment_sections(&elf, 1), ".interp");
+    assert_eq!(
+        get_segment_sections(&elf, 2),
+        ".interp .note.ABI-tag .gnu.hash .dynsym .dynstr .gnu.version .gnu.version_r \
+         .rela.dyn .rela.plt .init .plt .text .fini .rodata .eh_frame_hdr .eh_frame"
+    );
+    assert_eq!(
+        get_segment_sections(&elf, 3),
+        ".ctors .dtors .jcr .dynamic .got .got.plt .data .bss"
+    );
+    assert_eq!(get_segment_sections(&elf, 4), ".dynamic");
+    assert_eq!(get_segment_sections(&elf, 5), ".note.ABI-tag");
+    assert_eq!(get_segment_sections(&elf, 6), ".eh_frame_hdr");
+    assert_eq!(get_segment_sections(&elf, 7), "");
+
+    let dynamic = elf.get_section_by_name(".dynamic").unwrap().get_index();
+    assert_eq!(elf.get_segments_for_section(dynamic), [3, 4]);
+    let comment = elf.get_section_by_name(".comment").unwrap().get_index();
+    assert!(elf.get_segments_for_section(comment).is_empty());
+
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_ppc64")?))?;
+    assert_eq!(get_segment_sections(&elf, 5), ".note.gnu.build-id .note.ABI-tag");
+    assert_eq!(
+        get_segment_sections(&elf, 3),
+        ".init_array .fini_array .data.rel.ro .dynamic .opd .plt .got .data .bss"
+    );
+    // PT_GNU_RELRO
+    assert_eq!(
+        get_segment_sections(&elf, 7),
+        ".init_array .fini_array .data.rel.ro .dynamic .opd .plt .got"
+    );
+
+    // The mapping of a saved file is preserved
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_32")?))?;
+    let mapping: Vec<String> =
+        (0..elf.get_segments().len()).map(|i| get_segment_sections(&elf, i)).collect();
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let image = writer.into_inner();
+    let elf = Elfio::from_bytes(&image)?;
+    for (i, sections) in mapping.iter().enumerate() {
+        assert_eq!(&get_segment_sections(&elf, i), sections);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn segment_data() -> io::Result<()> {
+    // An executable without the section header table
+    let mut original = std::fs::read("tests/files/hello_64")?;
+    original.truncate(0x85c);
+    original[0x28..0x30].copy_from_slice(&[0; 8]);
+    original[0x3c..0x40].copy_from_slice(&[0; 4]);
+
+    let mut elf = Elfio::from_bytes(&original)?;
+    assert!(elf.get_sections().is_empty());
+    let segments = elf.get_segments();
+    assert_eq!(segments[1].get_data(), b"/lib64/ld-linux-x86-64.so.2\0");
+    assert_eq!(segments[2].get_data(), &original[..0x66c]);
+    assert_eq!(segments[3].get_data().len(), 0x1ec);
+    let (image, zeros) = segments[3].get_memory_image();
+    assert_eq!(image, &original[0x670..0x85c]);
+    assert_eq!(zeros, 0x200 - 0x1ec);
+    assert!(segments[7].get_data().is_empty());
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    assert!(writer.into_inner() == original);
+
+    // The segments of the loaded file refer to the same file content
+    let mut loaded = Elfio::new();
+    loaded.load(&mut io::Cursor::new(&original))?;
+    for (segment, loaded_segment) in elf.get_segments().iter().zip(loaded.get_segments()) {
+        assert_eq!(segment.get_data(), loaded_segment.get_data());
+    }
+
+    // A core dump like file
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS32, elfio::constant::ELFDATA2MSB);
+    elf.set_type(elfio::constant::ET_CORE);
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_NOTE);
+    segment.set_data(&[0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 1, b'C', b'O', b'R', 0]);
+    segment.set_align(4);
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_LOAD);
+    segment.set_virtual_address(0x10010);
+    segment.set_align(0x1000);
+    segment.set_memory_size(0x20);
+    segment.set_data(&[1, 2, 3, 4]);
+    assert_eq!(segment.get_file_size(), 4);
+    assert_eq!(segment.get_memory_size(), 0x20);
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_LOAD);
+    segment.set_offset(0x3000);
+    segment.set_data(&[5; 8]);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let mut elf = Elfio::new();
+    elf.load_lazy(io::Cursor::new(writer.into_inner()))?;
+    let segments = elf.get_segments();
+    assert_eq!(segments.len(), 3);
+    assert_eq!(&segments[0].get_data()[12..], b"COR\0");
+    assert_eq!(segments[1].get_offset() % 0x1000, 0x10);
+    assert_eq!(segments[1].get_data(), [1, 2, 3, 4]);
+    assert_eq!(segments[1].get_memory_image(), (&[1, 2, 3, 4][..], 0x1c));
+    assert_eq!(segments[2].get_offset(), 0x3000);
+    assert_eq!(segments[2].get_data(), [5; 8]);
+
+    Ok(())
+}
+
+#[test]
+fn write_exe_le_64() -> io::Result<()> {
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS64, elfio::constant::ELFDATA2LSB);
+    elf.set_type(elfio::constant::ET_EXEC);
+    elf.set_machine(62);
+
+    // mov eax, 60; mov edi, 42; syscall
+    let text_data = [
+        0xB8, 0x3C, 0x00, 0x00, 0x00, 0xBF, 0x2A, 0x00, 0x00, 0x00, 0x0F, 0x05,
+    ];
+    let text = elf.add_section(".text");
+    text.set_type(elfio::constant::SHT_PROGBITS);
+    text.set_flags(elfio::constant::SHF_ALLOC | elfio::constant::SHF_EXECINSTR);
+    text.set_addr_align(0x10);
+    text.set_data(&text_data);
+    let text_index = text.get_index();
+
+    let data = elf.add_section(".data");
+    data.set_type(elfio::constant::SHT_PROGBITS);
+    data.set_flags(elfio::constant::SHF_ALLOC | elfio::constant::SHF_WRITE);
+    data.set_addr_align(8);
+    data.set_data(b"Hello, World!\n");
+    let data_index = data.get_index();
+
+    let bss = elf.add_section(".bss");
+    bss.set_type(elfio::constant::SHT_NOBITS);
+    bss.set_flags(elfio::constant::SHF_ALLOC | elfio::constant::SHF_WRITE);
+    bss.set_addr_align(16);
+    bss.set_size(0x100);
+    let bss_index = bss.get_index();
+
+    let comment = elf.add_section(".comment");
+    comment.set_type(elfio::constant::SHT_PROGBITS);
+    comment.set_addr_align(1);
+    comment.set_data(b"elfio\0");
+
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_LOAD);
+    segment.set_virtual_address(0x400000);
+    segment.set_physical_address(0x400000);
+    segment.set_flags(elfio::constant::PF_R | elfio::constant::PF_X);
+    segment.set_align(0x1000);
+    assert!(elf.add_section_to_segment(0, text_index));
+
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_LOAD);
+    segment.set_virtual_address(0x600000);
+    segment.set_physical_address(0x600000);
+    segment.set_flags(elfio::constant::PF_R | elfio::constant::PF_W);
+    segment.set_align(0x1000);
+    assert!(elf.add_section_to_segment(1, data_index));
+    assert!(elf.add_section_to_segment(1, bss_index));
+    assert!(!elf.add_section_to_segment(2, data_index));
+    assert!(!elf.add_section_to_segment(1, 100));
+
+    // The sizes are known before the layout
+    let segment = elf.get_segment_by_index(1).unwrap();
+    assert_eq!(segment.get_file_size(), 14);
+    assert_eq!(segment.get_memory_size(), 0x110);
+    assert_eq!(segment.get_align(), 0x1000);
+
+    elf.set_entry(0x400000);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+
+    let mut reader = io::Cursor::new(writer.into_inner());
+    let mut elf = Elfio::new();
+    elf.load(&mut reader)?;
+
+    assert_eq!(elf.get_segments_offset(), 64);
+    assert_eq!(elf.get_sections_num(), 6);
+    assert_eq!(elf.get_section_name_str_index(), 5);
+
+    let sections = elf.get_sections();
+    let segments = elf.get_segments();
+    assert_eq!(segments.len(), 2);
+
+    for segment in segments {
+        assert_eq!(
+            segment.get_offset() % segment.get_align(),
+            segment.get_virtual_address() % segment.get_align()
+        );
+    }
+
+    let text = &sections[text_index as usize];
+    assert_eq!(text.get_data(), text_data);
+    assert_eq!(text.get_address(), 0x400000);
+    assert_eq!(text.get_offset() % 0x10, 0);
+    assert_eq!(segments[0].get_offset(), text.get_offset());
+    assert_eq!(segments[0].get_file_size(), text_data.len() as u64);
+    assert_eq!(segments[0].get_memory_size(), text_data.len() as u64);
+
+    let data = &sections[data_index as usize];
+    assert_eq!(data.get_data(), b"Hello, World!\n");
+    assert_eq!(data.get_address(), 0x600000);
+    assert_eq!(segments[1].get_offset(), data.get_offset());
+    assert!(data.get_offset() >= text.get_offset() + text.get_size());
+
+    let bss = &sections[bss_index as usize];
+    assert_eq!(bss.get_address(), 0x600010);
+    assert_eq!(segments[1].get_file_size(), 14);
+    assert_eq!(segments[1].get_memory_size(), 0x110);
+
+    let comment = &sections[4];
+    assert_eq!(comment.get_name(), ".comment");
+    assert_eq!(comment.get_data(), b"elfio\0");
+    assert!(comment.get_offset() >= data.get_offset() + data.get_size());
+    assert!(elf.get_sections_offset() >= comment.get_offset() + comment.get_size());
+    assert_eq!(elf.get_sections_offset() % 8, 0);
+
+    Ok(())
+}
+
+fn add_alloc_section(
+    elf: &mut Elfio,
+    name: &str,
+    stype: u32,
+    flags: u64,
+    data: &[u8],
+) -> u32 {
+    let section = elf.add_section(name);
+    section.set_type(stype);
+    section.set_flags(elfio::constant::SHF_ALLOC | flags);
+    section.set_addr_align(8);
+    if stype == elfio::constant::SHT_NOBITS {
+        section.set_size(data.len() as u64);
+    } else {
+        section.set_data(data);
+    }
+    section.get_index()
+}
+
+#[test]
+fn write_exe_segments() -> io::Result<()> {
+    use elfio::constant::*;
+
+    let mut elf = Elfio::new_(ELFCLASS64, ELFDATA2LSB);
+    elf.set_type(ET_EXEC);
+    elf.set_machine(62);
+
+    let interp = add_alloc_section(&mut elf, ".interp", SHT_PROGBITS, 0, b"/lib/ld.so\0");
+    let note = add_alloc_section(&mut elf, ".note", SHT_NOTE, 0, &[0; 16]);
+    let text = add_alloc_section(&mut elf, ".text", SHT_PROGBITS, SHF_EXECINSTR, &[0x90; 0x30]);
+    let tdata = add_alloc_section(&mut elf, ".tdata", SHT_PROGBITS, SHF_WRITE | SHF_TLS, &[1; 8]);
+    let tbss = add_alloc_section(&mut elf, ".tbss", SHT_NOBITS, SHF_WRITE | SHF_TLS, &[0; 0x10]);
+    let dynamic = add_alloc_section(&mut elf, ".dynamic", SHT_DYNAMIC, SHF_WRITE, &[0; 0x20]);
+    let data = add_alloc_section(&mut elf, ".data", SHT_PROGBITS, SHF_WRITE, &[3; 0x10]);
+    let bss = add_alloc_section(&mut elf, ".bss", SHT_NOBITS, SHF_WRITE, &[0; 0x100]);
+
+    let segment = elf.add_segment();
+    segment.set_type(PT_PHDR);
+    segment.set_flags(PF_R);
+    segment.set_align(8);
+    let segment = elf.add_segment();
+    segment.set_type(PT_INTERP);
+    segment.set_flags(PF_R);
+    elf.add_section_to_segment(1, interp);
+    // The first loadable segment contains the file and program headers
+    let segment = elf.add_segment();
+    segment.set_type(PT_LOAD);
+    segment.set_flags(PF_R | PF_X);
+    segment.set_align(0x1000);
+    segment.set_offset(0);
+    segment.set_virtual_address(0x400000);
+    segment.set_physical_address(0x400000);
+    for index in [interp, note, text].iter() {
+        elf.add_section_to_segment(2, *index);
+    }
+    let segment = elf.add_segment();
+    segment.set_type(PT_LOAD);
+    segment.set_flags(PF_R | PF_W);
+    segment.set_align(0x1000);
+    segment.set_virtual_address(0x601000);
+    segment.set_physical_address(0x601000);
+    for index in [tdata, tbss, dynamic, data, bss].iter() {
+        elf.add_section_to_segment(3, *index);
+    }
+    let segment = elf.add_segment();
+    segment.set_type(PT_DYNAMIC);
+    segment.set_flags(PF_R | PF_W);
+    elf.add_section_to_segment(4, dynamic);
+    let segment = elf.add_segment();
+    segment.set_type(PT_NOTE);
+    segment.set_flags(PF_R);
+    elf.add_section_to_segment(5, note);
+    let segment = elf.add_segment();
+    segment.set_type(PT_TLS);
+    segment.set_flags(PF_R);
+    elf.add_section_to_segment(6, tdata);
+    elf.add_section_to_segment(6, tbss);
+    let segment = elf.add_segment();
+    segment.set_type(PT_GNU_STACK);
+    segment.set_flags(PF_R | PF_W);
+    segment.set_align(16);
+    let segment = elf.add_segment();
+    segment.set_type(PT_GNU_RELRO);
+    segment.set_flags(PF_R);
+    for index in [tdata, tbss, dynamic].iter() {
+        elf.add_section_to_segment(8, *index);
+    }
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let image = writer.into_inner();
+    let mut elf = Elfio::from_bytes(&image)?;
+
+    let sections = elf.get_sections();
+    let segments = elf.get_segments();
+    let section = |index: u32| &sections[index as usize];
+    assert_eq!(segments[0].get_offset(), 64);
+    assert_eq!(segments[0].get_virtual_address(), 0x400040);
+    assert_eq!(segments[0].get_file_size(), 9 * 56);
+    assert_eq!(segments[1].get_offset(), section(interp).get_offset());
+    assert_eq!(segments[1].get_virtual_address(), section(interp).get_address());
+    assert_eq!(segments[1].get_file_size(), 11);
+    assert_eq!(section(interp).get_offset(), 64 + 9 * 56);
+    assert_eq!(segments[2].get_offset(), 0);
+    assert_eq!(segments[2].get_file_size(), section(text).get_offset() + 0x30);
+    assert_eq!(segments[3].get_offset() % 0x1000, 0);
+    assert_eq!(segments[3].get_file_size(), section(data).get_offset() + 0x10 - 0x1000);
+    assert_eq!(segments[3].get_memory_size(), section(bss).get_address() + 0x100 - 0x601000);
+    assert_eq!(segments[4].get_virtual_address(), section(dynamic).get_address());
+    assert_eq!(segments[4].get_file_size(), 0x20);
+    assert_eq!(segments[5].get_virtual_address(), section(note).get_address());
+    assert_eq!(segments[6].get_virtual_address(), 0x601000);
+    assert_eq!(segments[6].get_file_size(), 8);
+    assert_eq!(segments[6].get_memory_size(), 0x18);
+    assert_eq!(segments[7].get_file_size(), 0);
+    assert_eq!(segments[7].get_flags(), PF_R | PF_W);
+    assert_eq!(segments[8].get_virtual_address(), 0x601000);
+    assert_eq!(segments[8].get_memory_size(), section(dynamic).get_address() + 0x20 - 0x601000);
+
+    // .tbss takes no space outside of PT_TLS segment
+    assert_eq!(segments[3].get_section_indices(), [tdata, dynamic, data, bss]);
+    assert_eq!(segments[6].get_section_indices(), [tdata, tbss]);
+    assert_eq!(segments[8].get_section_indices(), [tdata, dynamic]);
+    assert_eq!(elf.get_segments_for_section(note), [2, 5]);
+
+    // A section added to a loaded segment extends it
+    let rodata = add_alloc_section(&mut elf, ".rodata", SHT_PROGBITS, 0, &[4; 0x10]);
+    assert!(elf.add_section_to_segment(2, rodata));
+    let text_end = elf.get_section_by_index(text).unwrap().get_offset() + 0x30;
+    let segment = elf.get_segment_by_index(2).unwrap();
+    assert_eq!(segment.get_file_size(), text_end + 0x10);
+    assert_eq!(segment.get_memory_size(), text_end + 0x10);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let image = writer.into_inner();
+    let elf = Elfio::from_bytes(&image)?;
+    let section = elf.get_section_by_index(rodata).unwrap();
+    assert_eq!(section.get_offset(), text_end);
+    assert_eq!(section.get_address(), 0x400000 + text_end);
+    let segment = elf.get_segment_by_index(2).unwrap();
+    assert_eq!(segment.get_offset(), 0);
+    assert_eq!(segment.get_file_size(), text_end + 0x10);
+    assert_eq!(segment.get_section_indices(), [interp, note, text, rodata]);
+
+    Ok(())
+}
+
+#[test]
+fn write_obj_be_32() -> io::Result<()> {
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS32, elfio::constant::ELFDATA2MSB);
+    elf.set_type(elfio::constant::ET_REL);
+    elf.set_machine(20);
+
+    let text = elf.add_section(".text");
+    text.set_type(elfio::constant::SHT_PROGBITS);
+    text.set_flags(elfio::constant::SHF_ALLOC | elfio::constant::SHF_EXECINSTR);
+    text.set_addr_align(4);
+    text.set_data(&[0x4E, 0x80, 0x00, 0x20, 0x60, 0x00]);
+
+    let rodata = elf.add_section(".rodata");
+    rodata.set_type(elfio::constant::SHT_PROGBITS);
+    rodata.set_flags(elfio::constant::SHF_ALLOC);
+    rodata.set_addr_align(16);
+    rodata.set_data(&[1, 2, 3]);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+
+    let mut reader = io::Cursor::new(writer.into_inner());
+    let mut elf = Elfio::new();
+    elf.load(&mut reader)?;
+
+    assert_eq!(elf.get_segments_num(), 0);
+    assert_eq!(elf.get_segments_offset(), 0);
+    assert_eq!(elf.get_sections_num(), 4);
+    assert_eq!(elf.get_section_name_str_index(), 3);
+
+    let sections = elf.get_sections();
+    assert_eq!(sections[0].get_type(), elfio::constant::SHT_NULL);
+    assert_eq!(sections[0].get_offset(), 0);
+    assert_eq!(sections[1].get_name(), ".text");
+    assert_eq!(sections[1].get_offset(), 52);
+    assert_eq!(sections[1].get_data(), [0x4E, 0x80, 0x00, 0x20, 0x60, 0x00]);
+    assert_eq!(sections[2].get_name(), ".rodata");
+    assert_eq!(sections[2].get_offset(), 64);
+    assert_eq!(sections[2].get_data(), [1, 2, 3]);
+    assert_eq!(sections[3].get_name(), ".shstrtab");
+    assert_eq!(sections[3].get_type(), elfio::constant::SHT_STRTAB);
+    assert_eq!(sections[3].get_offset(), 67);
+    assert_eq!(sections[3].get_data(), b"\0.text\0.rodata\0.shstrtab\0");
+    assert_eq!(elf.get_sections_offset(), 92);
+
+    Ok(())
+}
+
+#[test]
+fn write_many_sections() -> io::Result<()> {
+    // More sections than fit into e_shnum and e_shstrndx
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS64, elfio::constant::ELFDATA2LSB);
+    elf.set_type(elfio::constant::ET_REL);
+    for i in 1..70000 {
+        let section = elf.add_section(&format!(".text.f{}", i));
+        section.set_type(elfio::constant::SHT_PROGBITS);
+    }
+    let strtab = elf.add_section(".strtab");
+    strtab.set_type(elfio::constant::SHT_STRTAB);
+    let strtab_index = strtab.get_index();
+    let symtab = elf.add_section(".symtab");
+    symtab.set_type(elfio::constant::SHT_SYMTAB);
+    symtab.set_link(strtab_index);
+    let symtab_index = symtab.get_index();
+
+    let mut symbols = elfio::SymbolSectionAccessorMut::new(&mut elf, symtab_index).unwrap();
+    // Sections in the reserved index range are referred through the extended
+    // section index table, special indices are stored as is
+    let symbols_to_add = [
+        ("f1", 1, false),
+        ("f69999", 69999, false),
+        ("f65296", 0xFF10, false),
+        ("abs", 0xFFF1, true),
+        ("f65521", 0xFFF1, false),
+    ];
+    for (name, shndx, special_shndx) in symbols_to_add.iter() {
+        let symbol = elfio::Symbol {
+            name: name.to_string(),
+            bind: elfio::constant::STB_GLOBAL,
+            shndx: *shndx,
+            special_shndx: *special_shndx,
+            ..Default::default()
+        };
+        symbols.add_symbol(&symbol, |_, _| ());
+    }
+    assert!(symbols.remove_symbol(1, |_, _| ()));
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let image = writer.into_inner();
+    let elf = Elfio::from_bytes(&image)?;
+
+    let sections = elf.get_sections();
+    assert_eq!(sections.len(), 70004);
+    assert_eq!(elf.get_sections_num(), 0);
+    assert_eq!(sections[0].get_size(), 70004);
+    assert_eq!(elf.get_section_name_str_index(), elfio::constant::SHN_XINDEX);
+    assert_eq!(sections[0].get_link(), 70003);
+    assert_eq!(sections[69999].get_name(), ".text.f69999");
+    assert_eq!(sections[70002].get_name(), ".symtab_shndx");
+    assert_eq!(sections[70002].get_data().len(), 20);
+
+    let symtab = elf.get_section_by_name(".symtab").unwrap();
+    let symbols = elfio::SymbolSectionAccessor::new(&elf, symtab);
+    let symbol = symbols.get_symbol(1).unwrap();
+    assert_eq!(symbol.name, "f69999");
+    assert_eq!(symbol.shndx, 69999);
+    assert!(!symbol.special_shndx);
+    let symbol = symbols.get_symbol(2).unwrap();
+    assert_eq!((symbol.shndx, symbol.special_shndx), (0xFF10, false));
+    assert_eq!(&symtab.get_data()[2 * 24 + 6..2 * 24 + 8], [0xFF, 0xFF]);
+    let symbol = symbols.get_symbol(3).unwrap();
+    assert_eq!((symbol.shndx, symbol.special_shndx), (0xFFF1, true));
+    let symbol = symbols.get_symbol(4).unwrap();
+    assert_eq!((symbol.shndx, symbol.special_shndx), (0xFFF1, false));
+
+    Ok(())
+}
+
+#[test]
+fn write_many_segments() -> io::Result<()> {
+    // More segments than fit into e_phnum, like in large core dumps
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS64, elfio::constant::ELFDATA2LSB);
+    elf.set_type(elfio::constant::ET_CORE);
+    for i in 0..70000 {
+        let segment = elf.add_segment();
+        segment.set_type(elfio::constant::PT_LOAD);
+        segment.set_virtual_address(0x10000 * i);
+        segment.set_memory_size(0x1000);
+    }
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let image = writer.into_inner();
+    let elf = Elfio::from_bytes(&image)?;
+
+    assert_eq!(elf.get_segments_num(), elfio::constant::PN_XNUM);
+    assert_eq!(elf.get_sections().len(), 2);
+    assert_eq!(elf.get_sections()[0].get_info(), 70000);
+    let segments = elf.get_segments();
+    assert_eq!(segments.len(), 70000);
+    assert_eq!(segments[69999].get_virtual_address(), 0x10000 * 69999);
+    assert_eq!(segments[69999].get_memory_size(), 0x1000);
+
+    // Unmodified file is written back as is
+    let mut elf = Elfio::from_bytes(&image)?;
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    assert!(writer.into_inner() == image);
+
+    Ok(())
+}
+
+#[test]
+fn layout_errors() -> io::Result<()> {
+    // A loaded section grown over the following one can't be moved out
+    // of its segment
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_64")?))?;
+    let text = elf.get_section_by_name_mut(".text").unwrap();
+    text.append_data(&[0x90; 0x100]);
+    let index = text.get_index();
+    assert!(matches!(
+        elf.save(&mut io::Cursor::new(Vec::new())),
+        Err(ElfioError::SectionOutOfSegment { section, .. }) if section == index
+    ));
+
+    // A loaded section grown past the end of its segment
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_64")?))?;
+    let bss = elf.get_section_by_name_mut(".bss").unwrap();
+    bss.set_size(bss.get_size() + 0x10000);
+    let index = bss.get_index();
+    assert!(matches!(
+        elf.save(&mut io::Cursor::new(Vec::new())),
+        Err(ElfioError::SectionOutOfSegment { section, .. }) if section == index
+    ));
+
+    // The section address doesn't fit into 64 bits
+    let mut elf = Elfio::new_(elfio::constant::ELFCLASS64, elfio::constant::ELFDATA2LSB);
+    let section = elf.add_section(".text");
+    section.set_type(elfio::constant::SHT_PROGBITS);
+    section.set_flags(elfio::constant::SHF_ALLOC);
+    section.set_data(&[0; 0x20]);
+    let index = section.get_index();
+    let segment = elf.add_segment();
+    segment.set_type(elfio::constant::PT_LOAD);
+    segment.set_virtual_address(u64::MAX - 0x10);
+    segment.set_align(0x1000);
+    elf.add_section_to_segment(0, index);
+    assert!(matches!(
+        elf.save(&mut io::Cursor::new(Vec::new())),
+        Err(ElfioError::LayoutOverflow)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rebuild_section_names() -> io::Result<()> {
+    let original = std::fs::read("tests/files/hello_32")?;
+    let mut elf = Elfio::from_bytes(&original)?;
+    let names: Vec<String> = elf
+        .get_sections()
+        .iter()
+        .map(|section| section.get_name().to_string())
+        .collect();
+
+    elf.add_section(".note.elfio")
+        .set_type(elfio::constant::SHT_NOTE);
+    elf.add_section(".text")
+        .set_type(elfio::constant::SHT_PROGBITS);
+    elf.get_section_by_name_mut(".comment")
+        .unwrap()
+        .set_name(".comment.renamed");
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+    let sections = elf.get_sections();
+    assert_eq!(sections.len(), names.len() + 2);
+    for (section, name) in sections.iter().zip(names.iter()) {
+        if name == ".comment" {
+            assert_eq!(section.get_name(), ".comment.renamed");
+        } else {
+            assert_eq!(section.get_name(), name);
+        }
+    }
+    assert_eq!(sections[names.len()].get_name(), ".note.elfio");
+    assert_eq!(sections[names.len() + 1].get_name(), ".text");
+
+    // Names of the same value share a string table entry
+    let text = elf.get_section_by_name(".text").unwrap();
+    assert_eq!(
+        sections[names.len() + 1].get_name_string_offset(),
+        text.get_name_string_offset()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_lazy() -> io::Result<()> {
+    for file_name in ["tests/files/hello_64", "tests/files/hello_ppc"].iter() {
+        let mut eager = Elfio::new();
+        eager.load(&mut BufReader::new(File::open(file_name)?))?;
+
+        let mut lazy = Elfio::new();
+        lazy.load_lazy(BufReader::new(File::open(file_name)?))?;
+
+        assert_eq!(lazy.get_sections().len(), eager.get_sections().len());
+        for (lazy_section, section) in lazy.get_sections().iter().zip(eager.get_sections()) {
+            assert_eq!(lazy_section.get_name(), section.get_name());
+            assert_eq!(lazy_section.get_data(), section.get_data());
+        }
+
+        let mut writer = io::Cursor::new(Vec::new());
+        lazy.save(&mut writer)?;
+        assert!(writer.get_ref() == &std::fs::read(file_name)?);
+    }
+
+    Ok(())
+}
+
+// A reader which fails to read while its failure flag is set
+struct FailingReader {
+    cursor: io::Cursor<Vec<u8>>,
+    fail:   std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+impl io::Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.fail.get() {
+            return Err(io::Error::other("read failure"));
+        }
+        self.cursor.read(buf)
+    }
+}
+
+impl io::Seek for FailingReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+impl elfio::ElfioReadSeek for FailingReader {}
+
+#[test]
+fn load_lazy_errors() -> io::Result<()> {
+    let fail = std::rc::Rc::new(std::cell::Cell::new(false));
+    let reader = FailingReader {
+        cursor: io::Cursor::new(std::fs::read("tests/files/hello_64")?),
+        fail:   fail.clone(),
+    };
+    let mut elf = Elfio::new();
+    elf.load_lazy(reader)?;
+    fail.set(true);
+
+    let symtab = elf.get_section_by_name(".symtab").unwrap();
+    let symtab_index = symtab.get_index();
+    assert!(symtab.get_data().is_empty());
+    assert!(symtab.try_get_data().is_err());
+    let symbols = elfio::SymbolSectionAccessor::new(&elf, symtab);
+    assert_eq!(symbols.get_symbols_num(), 0);
+    assert!(symbols.get_symbol(1).is_none());
+    assert!(elf.get_segments()[2].try_get_data().is_err());
+    assert!(elfio::SymbolSectionAccessorMut::new(&mut elf, symtab_index).is_none());
+    assert!(elf.save(&mut io::Cursor::new(Vec::new())).is_err());
+
+    // The read errors are not cached
+    fail.set(false);
+    let symtab = elf.get_section_by_index(symtab_index).unwrap();
+    assert!(!symtab.try_get_data()?.is_empty());
+    assert!(elfio::SymbolSectionAccessorMut::new(&mut elf, symtab_index).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn from_bytes() -> io::Result<()> {
+    for file_name in ["tests/files/hello_32", "tests/files/hello_ppc64"].iter() {
+        let image = std::fs::read(file_name)?;
+        let image_range = image.as_ptr_range();
+
+        let mut eager = Elfio::new();
+        eager.load(&mut io::Cursor::new(&image[..]))?;
+
+        let mut elf = Elfio::from_bytes(&image)?;
+        assert_eq!(elf.get_sections().len(), eager.get_sections().len());
+        for (section, eager_section) in elf.get_sections().iter().zip(eager.get_sections()) {
+            assert_eq!(section.get_name(), eager_section.get_name());
+            assert_eq!(section.get_data(), eager_section.get_data());
+            if !section.get_data().is_empty() {
+                assert!(image_range.contains(&section.get_data().as_ptr()));
+            }
+        }
+
+        let mut writer = io::Cursor::new(Vec::new());
+        elf.save(&mut writer)?;
+        assert!(writer.get_ref() == &image);
+    }
+
+    let image = std::fs::read("tests/files/hello_64")?;
+    let elf = Elfio::from_bytes(&image)?;
+    let section = elf.get_section_by_name(".symtab").unwrap();
+    let symbols = elfio::SymbolSectionAccessor::new(&elf, section);
+    assert_eq!(symbols.get_symbols_num(), 67);
+    assert_eq!(symbols.get_symbol(33).unwrap().name, "__do_global_dtors_aux");
+
+    assert!(matches!(
+        Elfio::from_bytes(&image[..100]),
+        Err(ElfioError::TruncatedSectionHeader { index: 0 })
+    ));
+
+    Ok(())
+}
+
+fn load_bytes(data: &[u8]) -> Result<Elfio<'static>, ElfioError> {
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(data))?;
+
+    Ok(elf)
+}
+
+#[test]
+fn load_errors() -> io::Result<()> {
+    let original = std::fs::read("tests/files/hello_64")?;
+
+    let mut data = original.clone();
+    data[1] = b'X';
+    assert!(matches!(load_bytes(&data), Err(ElfioError::BadMagic)));
+    assert!(matches!(
+        load_bytes(&[]),
+        Err(ElfioError::TruncatedFileHeader)
+    ));
+
+    let mut data = original.clone();
+    data[elfio::constant::EI_CLASS] = 3;
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::UnsupportedClass(3))
+    ));
+
+    let mut data = original.clone();
+    data[elfio::constant::EI_DATA] = 7;
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::UnsupportedEncoding(7))
+    ));
+
+    assert!(matches!(
+        load_bytes(&original[..40]),
+        Err(ElfioError::TruncatedFileHeader)
+    ));
+
+    let elf = load_bytes(&original).unwrap();
+    let shoff = elf.get_sections_offset() as usize;
+    let offset = elf.get_section_by_index(1).unwrap().get_offset();
+    assert!(matches!(
+        load_bytes(&original[..shoff + 2 * 64 + 10]),
+        Err(ElfioError::TruncatedSectionHeader { index: 2 })
+    ));
+
+    let mut data = original.clone();
+    data[shoff + 64 + 32..shoff + 64 + 40].copy_from_slice(&0x10000u64.to_le_bytes());
+    match load_bytes(&data) {
+        Err(ElfioError::SectionDataOutOfBounds {
+            index: 1,
+            offset: o,
+            size: 0x10000,
+        }) => assert_eq!(o, offset),
+        _ => panic!("section data out of bounds is not reported"),
+    }
+
+    // p_filesz of the PT_INTERP segment
+    let mut data = original.clone();
+    data[64 + 56 + 32..64 + 56 + 40].copy_from_slice(&0x10000u64.to_le_bytes());
+    match load_bytes(&data) {
+        Err(ElfioError::SegmentDataOutOfBounds {
+            index: 1,
+            offset: 0x200,
+            size: 0x10000,
+        }) => (),
+        _ => panic!("segment data out of bounds is not reported"),
+    }
+
+    // Extended section numbering: e_shnum is 0 and sh_size of the section 0
+    // claims a huge number of sections
+    let mut data = vec![0u8; 128];
+    data[..4].copy_from_slice(b"\x7FELF");
+    data[elfio::constant::EI_CLASS] = elfio::constant::ELFCLASS64;
+    data[elfio::constant::EI_DATA] = elfio::constant::ELFDATA2LSB;
+    data[elfio::constant::EI_VERSION] = 1;
+    data[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+    data[0x34..0x36].copy_from_slice(&64u16.to_le_bytes());
+    data[64 + 32..64 + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::UnsupportedSectionEntrySize(0))
+    ));
+    data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::TruncatedSectionHeader { index: 1 })
+    ));
+
+    // e_phnum is PN_XNUM and sh_info of the section 0 claims a huge number
+    // of segments
+    let mut data = vec![0u8; 192];
+    data[..4].copy_from_slice(b"\x7FELF");
+    data[elfio::constant::EI_CLASS] = elfio::constant::ELFCLASS64;
+    data[elfio::constant::EI_DATA] = elfio::constant::ELFDATA2LSB;
+    data[elfio::constant::EI_VERSION] = 1;
+    data[0x20..0x28].copy_from_slice(&128u64.to_le_bytes());
+    data[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+    data[0x34..0x36].copy_from_slice(&64u16.to_le_bytes());
+    data[0x38..0x3A].copy_from_slice(&elfio::constant::PN_XNUM.to_le_bytes());
+    data[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+    data[0x3C..0x3E].copy_from_slice(&1u16.to_le_bytes());
+    data[64 + 44..64 + 48].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::UnsupportedSegmentEntrySize(0))
+    ));
+    data[0x36..0x38].copy_from_slice(&56u16.to_le_bytes());
+    assert!(matches!(
+        load_bytes(&data),
+        Err(ElfioError::TruncatedSegmentHeader { index: 1 })
+    ));
+
+    let e: io::Error = ElfioError::BadMagic.into();
+    assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+    Ok(())
+}
@@ -20,6 +20,13 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN
 THE SOFTWARE.
 */
 
+#![allow(
+    clippy::borrow_deref_ref,
+    clippy::io_other_error,
+    clippy::needless_borrow,
+    clippy::unnecessary_to_owned
+)]
+
 use std::fs::File;
 use std::io;
 use std::io::{BufReader, Error};
@@ -633,6 +640,332 @@ fn array_be_64() -> io::Result<()> {
     Ok(())
 }
 
+#[test]
+fn find_symbols() -> io::Result<()> {
+    // hello_ppc has a SysV hash table, the others have GNU hash tables
+    for file in ["hello_32", "hello_64", "hello_ppc", "hello_ppc64"].iter() {
+        let image = std::fs::read(format!("tests/files/{}", file))?;
+        let elf = Elfio::from_bytes(&image)?;
+
+        let dynsym = elf.get_section_by_name(".dynsym").unwrap();
+        let symbols = SymbolSectionAccessor::new(&elf, dynsym);
+        for index in 1..symbols.get_symbols_num() {
+            let name = symbols.get_symbol(index).unwrap().name;
+            if name.is_empty() {
+                continue;
+            }
+            let (found, symbol) = symbols.find_by_name(&name).unwrap();
+            assert_eq!(found, index, "{} in {}", name, file);
+            assert_eq!(symbol.name, name);
+        }
+        assert!(symbols.find_by_name("no_such_symbol").is_none());
+    }
+
+    let image = std::fs::read("tests/files/hello_ppc64")?;
+    let elf = Elfio::from_bytes(&image)?;
+    let dynsym = elf.get_section_by_name(".dynsym").unwrap();
+    let symbols = SymbolSectionAccessor::new(&elf, dynsym);
+    assert_eq!(symbols.find_by_name("error").unwrap().0, 24);
+    assert_eq!(symbols.find_by_name("strlen").unwrap().0, 3);
+
+    let image = std::fs::read("tests/files/hello_64")?;
+    let elf = Elfio::from_bytes(&image)?;
+    let symtab = elf.get_section_by_name(".symtab").unwrap();
+    let symbols = SymbolSectionAccessor::new(&elf, symtab);
+    // 65: 0000000000400498    21 FUNC    GLOBAL DEFAULT   12 main
+    assert_eq!(symbols.find_by_name("main").unwrap().0, 65);
+    assert_eq!(symbols.find_by_address(0x400498).unwrap().0, 65);
+    assert_eq!(symbols.find_by_address(0x4004ac).unwrap().1.name, "main");
+    assert!(symbols.find_by_address(0x4004ad).is_none());
+    // 61: 00000000004004c0   139 FUNC    GLOBAL DEFAULT   12 __libc_csu_init
+    assert_eq!(symbols.find_by_address(0x40054a).unwrap().0, 61);
+    assert!(symbols.find_by_address(0x40054b).is_none());
+    // Undefined symbols are not located by the address
+    assert!(symbols.find_by_address(0).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn generate_hash_tables() -> io::Result<()> {
+    let elf_file = File::open("tests/files/hello_ppc")?;
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(elf_file))?;
+
+    let dynsym_index = elf.get_section_by_name(".dynsym").unwrap().get_index();
+    let hash_index = elf.get_section_by_name(".hash").unwrap().get_index();
+    let mut dynsym = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+    for name in ["new_data", "new_func"].iter() {
+        let symbol = Symbol {
+            name: name.to_string(),
+            bind: constant::STB_GLOBAL,
+            shndx: 13,
+            ..Default::default()
+        };
+        dynsym.add_symbol(&symbol, |_, _| ());
+    }
+    assert!(HashSectionAccessorMut::new(&mut elf, hash_index).unwrap().generate());
+
+    let hash = HashSectionAccessor::new(&elf, elf.get_section_by_index(hash_index).unwrap());
+    let dynsym = SymbolSectionAccessor::new(&elf, elf.get_section_by_index(dynsym_index).unwrap());
+    assert_eq!(hash.get_chains_num(), 15);
+    for index in 1..dynsym.get_symbols_num() {
+        let name = dynsym.get_symbol(index).unwrap().name;
+        assert_eq!(hash.find_symbol(&name).unwrap().0, index);
+    }
+
+    let elf_file = File::open("tests/files/hello_ppc64")?;
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(elf_file))?;
+
+    let dynsym_index = elf.get_section_by_name(".dynsym").unwrap().get_index();
+    let hash_index = elf.get_section_by_name(".gnu.hash").unwrap().get_index();
+    let mut dynsym = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+    for (name, shndx) in [("new_func", 21), ("new_undef", constant::SHN_UNDEF as ElfWord)].iter() {
+        let symbol = Symbol {
+            name: name.to_string(),
+            bind: constant::STB_GLOBAL,
+            shndx: *shndx,
+            ..Default::default()
+        };
+        dynsym.add_symbol(&symbol, |_, _| ());
+    }
+
+    let mut moved = Vec::new();
+    let mut hash = GnuHashSectionAccessorMut::new(&mut elf, hash_index).unwrap();
+    assert!(hash.generate(|old, new| moved.push((old, new))));
+    assert_eq!(moved, [(26, 24), (24, 25), (25, 26)]);
+
+    let hash = GnuHashSectionAccessor::new(&elf, elf.get_section_by_index(hash_index).unwrap());
+    assert_eq!(hash.get_symbol_offset(), 25);
+    assert_eq!(hash.get_chains_num(), 2);
+    assert_eq!(hash.find_symbol("error").unwrap().0, 25);
+    assert_eq!(hash.find_symbol("new_func").unwrap().0, 26);
+    assert!(hash.find_symbol("new_undef").is_none());
+
+    let dynsym = SymbolSectionAccessor::new(&elf, elf.get_section_by_index(dynsym_index).unwrap());
+    assert_eq!(dynsym.find_by_name("new_undef").unwrap().0, 24);
+    assert_eq!(dynsym.find_by_name("new_func").unwrap().0, 26);
+
+    Ok(())
+}
+
+#[test]
+fn generate_gnu_hash_keeps_linked_tables() -> io::Result<()> {
+    let elf_file = File::open("tests/files/hello_ppc64")?;
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(elf_file))?;
+
+    let dynsym_index = elf.get_section_by_name(".dynsym").unwrap().get_index();
+    let gnu_hash_index = elf.get_section_by_name(".gnu.hash").unwrap().get_index();
+    let versym_index = elf.get_section_by_name(".gnu.version").unwrap().get_index();
+    let hash = elf.add_section(".hash");
+    hash.set_type(constant::SHT_HASH);
+    hash.set_link(dynsym_index);
+    hash.set_entry_size(4);
+    let hash_index = hash.get_index();
+
+    let mut dynsym = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+    for (name, shndx) in [("new_func", 21), ("new_undef", constant::SHN_UNDEF as ElfWord)].iter() {
+        let symbol = Symbol {
+            name: name.to_string(),
+            bind: constant::STB_GLOBAL,
+            shndx: *shndx,
+            ..Default::default()
+        };
+        dynsym.add_symbol(&symbol, |_, _| ());
+    }
+    // Version entries of the new symbols: new_func is global, new_undef is local
+    let versym = elf.get_section_by_index_mut(versym_index).unwrap();
+    let mut data = versym.get_data().to_vec();
+    data.extend_from_slice(&[0, 1, 0, 0]);
+    versym.set_data(&data);
+    assert!(HashSectionAccessorMut::new(&mut elf, hash_index).unwrap().generate());
+
+    let versions = |elf: &Elfio| -> Vec<(String, ElfHalf)> {
+        let dynsym = elf.get_section_by_index(dynsym_index).unwrap();
+        let dynsym = SymbolSectionAccessor::new(elf, dynsym);
+        let versym = elf.get_section_by_index(versym_index).unwrap();
+        let versym = VersymSectionAccessor::new(elf, versym);
+        let mut versions: Vec<(String, ElfHalf)> = (0..dynsym.get_symbols_num())
+            .map(|i| (dynsym.get_symbol(i).unwrap().name, versym.get_entry(i).unwrap()))
+            .collect();
+        versions.sort();
+        versions
+    };
+    let before = versions(&elf);
+
+    let mut moved = Vec::new();
+    let mut gnu_hash = GnuHashSectionAccessorMut::new(&mut elf, gnu_hash_index).unwrap();
+    assert!(gnu_hash.generate(|old, new| moved.push((old, new))));
+    assert_eq!(moved, [(26, 24), (24, 25), (25, 26)]);
+    assert_eq!(versions(&elf), before);
+
+    let hash = HashSectionAccessor::new(&elf, elf.get_section_by_index(hash_index).unwrap());
+    let dynsym = SymbolSectionAccessor::new(&elf, elf.get_section_by_index(dynsym_index).unwrap());
+    // Section symbols have no names
+    for index in 1..dynsym.get_symbols_num() {
+        let name = dynsym.get_symbol(index).unwrap().name;
+        if !name.is_empty() {
+            assert_eq!(hash.find_symbol(&name).unwrap().0, index);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn symbol_versions() -> io::Result<()> {
+    let image = std::fs::read("tests/files/hello_ppc")?;
+    let elf = Elfio::from_bytes(&image)?;
+
+    let versym = elf.get_section_by_name(".gnu.version").unwrap();
+    let versym = VersymSectionAccessor::new(&elf, versym);
+    assert_eq!(versym.get_entries_num(), 13);
+    assert_eq!(versym.get_entry(12), Some(5));
+    assert_eq!(versym.get_entry(13), None);
+
+    let verneed = elf.get_section_by_name(".gnu.version_r").unwrap();
+    let verneed = VerneedSectionAccessor::new(&elf, verneed);
+    assert_eq!(verneed.get_entries_num(), 2);
+    let requirements = verneed.get_entries();
+    assert_eq!(requirements[1].version, 1);
+    assert_eq!(requirements[1].file, "libc.so.6");
+    let names: Vec<&str> = requirements[1].versions.iter().map(|v| v.name.as_str()).collect();
+    assert_eq!(names, ["GLIBC_2.0", "GLIBC_2.1.3"]);
+    assert_eq!(requirements[1].versions[1].index, 2);
+    assert_eq!(requirements[1].versions[1].flags, 0);
+    assert_eq!(verneed.find_by_index(3).unwrap().name, "GLIBCXX_3.4");
+    assert!(verneed.find_by_index(6).is_none());
+
+    // 12: 10000960  1420 FUNC    GLOBAL DEFAULT  UND __gxx_personality_v0@CXXABI_1.3 (5)
+    let dynsym = elf.get_section_by_name(".dynsym").unwrap();
+    let dynsym = SymbolSectionAccessor::new(&elf, dynsym);
+    let version = dynsym.get_symbol(12).unwrap().version.unwrap();
+    assert_eq!(version.name, "CXXABI_1.3");
+    assert!(!version.hidden);
+    assert!(dynsym.get_symbol(2).unwrap().version.is_none());
+    assert!(dynsym.get_symbol(8).unwrap().version.is_none());
+
+    // Symbols of .symtab are not versioned
+    let symtab = elf.get_section_by_name(".symtab").unwrap();
+    let symtab = SymbolSectionAccessor::new(&elf, symtab);
+    assert!((0..symtab.get_symbols_num()).all(|i| symtab.get_symbol(i).unwrap().version.is_none()));
+
+    Ok(())
+}
+
+#[test]
+fn symbol_version_definitions() {
+    let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2MSB);
+    let dynstr = elf.add_section(".dynstr");
+    dynstr.set_type(constant::SHT_STRTAB);
+    let dynstr_index = dynstr.get_index();
+    let dynsym = elf.add_section(".dynsym");
+    dynsym.set_type(constant::SHT_DYNSYM);
+    dynsym.set_link(dynstr_index as ElfWord);
+    let dynsym_index = dynsym.get_index();
+
+    let mut symbols = SymbolSectionAccessorMut::new(&mut elf, dynsym_index).unwrap();
+    for name in ["foo", "foo"].iter() {
+        let symbol = Symbol {
+            name: name.to_string(),
+            bind: constant::STB_GLOBAL,
+            shndx: 1,
+            ..Default::default()
+        };
+        symbols.add_symbol(&symbol, |_, _| ());
+    }
+    let mut strings = StringSectionAccessorMut::new(elf.get_section_by_index_mut(1).unwrap());
+    let base = strings.add_string("libfoo.so");
+    let v1 = strings.add_string("FOO_1.0");
+    let v2 = strings.add_string("FOO_2.0");
+
+    let versym = elf.add_section(".gnu.version");
+    versym.set_type(constant::SHT_GNU_VERSYM);
+    versym.set_link(dynsym_index as ElfWord);
+    versym.set_data(&[0, 0, 0x80, 2, 0, 3]);
+
+    let verdef = elf.add_section(".gnu.version_d");
+    verdef.set_type(constant::SHT_GNU_VERDEF);
+    verdef.set_link(dynstr_index as ElfWord);
+    verdef.set_info(3);
+    let mut data = Vec::new();
+    // Elf_Verdef: version, flags, index, aux count, hash, aux, next
+    // followed by Elf_Verdaux entries: name, next
+    let definitions = [(1, 1, base, None), (0, 2, v1, None), (0, 3, v2, Some(v1))];
+    for (i, (flags, index, name, parent)) in definitions.iter().enumerate() {
+        let count: u16 = if parent.is_some() { 2 } else { 1 };
+        let next: u32 = if i + 1 < definitions.len() { 20 + 8 * count as u32 } else { 0 };
+        data.extend_from_slice(&1u16.to_be_bytes());
+        data.extend_from_slice(&(*flags as u16).to_be_bytes());
+        data.extend_from_slice(&(*index as u16).to_be_bytes());
+        data.extend_from_slice(&count.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(&20u32.to_be_bytes());
+        data.extend_from_slice(&next.to_be_bytes());
+        data.extend_from_slice(&name.to_be_bytes());
+        data.extend_from_slice(&(if parent.is_some() { 8u32 } else { 0 }).to_be_bytes());
+        if let Some(parent) = parent {
+            data.extend_from_slice(&parent.to_be_bytes());
+            data.extend_from_slice(&0u32.to_be_bytes());
+        }
+    }
+    verdef.set_data(&data);
+    let verdef_index = verdef.get_index();
+
+    let verdef = VerdefSectionAccessor::new(&elf, elf.get_section_by_index(verdef_index).unwrap());
+    let definitions = verdef.get_entries();
+    assert_eq!(definitions.len(), 3);
+    assert_eq!(definitions[0].flags, constant::VER_FLG_BASE);
+    assert_eq!(definitions[0].names, ["libfoo.so"]);
+    assert_eq!(definitions[2].names, ["FOO_2.0", "FOO_1.0"]);
+    assert_eq!(verdef.find_by_index(2).unwrap().names, ["FOO_1.0"]);
+
+    let dynsym = SymbolSectionAccessor::new(&elf, elf.get_section_by_index(dynsym_index).unwrap());
+    let hidden = SymbolVersion {
+        name:   "FOO_1.0".to_string(),
+        hidden: true,
+    };
+    let default = SymbolVersion {
+        name:   "FOO_2.0".to_string(),
+        hidden: false,
+    };
+    assert_eq!(dynsym.get_symbol(1).unwrap().version, Some(hidden));
+    assert_eq!(dynsym.get_symbol(2).unwrap().version, Some(default));
+}
+
+#[test]
+fn symbol_version_overlapping_entries() {
+    let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+    // Every entry and every auxiliary entry refers to the next byte,
+    // the first entry claims the maximum number of auxiliary entries
+    let mut data = vec![1u8; 400];
+    data[2..4].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    data[6..8].copy_from_slice(&0xFFFFu16.to_le_bytes());
+    for (name, section_type) in [
+        (".gnu.version_d", constant::SHT_GNU_VERDEF),
+        (".gnu.version_r", constant::SHT_GNU_VERNEED),
+    ]
+    .iter()
+    {
+        let section = elf.add_section(name);
+        section.set_type(*section_type);
+        section.set_info(0xFFFF_FFFF);
+        section.set_data(&data);
+    }
+
+    let section = elf.get_section_by_name(".gnu.version_d").unwrap();
+    let definitions = VerdefSectionAccessor::new(&elf, section).get_entries();
+    assert!(definitions.len() <= 400 / 20);
+    assert!(definitions.iter().map(|entry| entry.names.len()).sum::<usize>() <= 400 / 8);
+
+    let section = elf.get_section_by_name(".gnu.version_r").unwrap();
+    let requirements = VerneedSectionAccessor::new(&elf, section).get_entries();
+    assert!(requirements.len() <= 400 / 16);
+    assert!(requirements.iter().map(|entry| entry.versions.len()).sum::<usize>() <= 400 / 16);
+}
+
 #[test]
 fn modinfo_le_32() -> io::Result<()> {
     let elf_file = File::open("tests/files/i2c-gpio.ko")?;
@@ -654,6 +987,893 @@ fn modinfo_le_32() -> io::Result<()> {
         modinfo.get(&"description".to_string()).unwrap(),
         "Platform-independent bitbanging I2C driver"
     );
+    assert_eq!(modinfo.get_iter().next().unwrap().0, "alias");
+    assert_eq!(modinfo.get_license(), Some("GPL"));
+    assert_eq!(
+        modinfo.get_vermagic(),
+        Some("5.11.0-22-generic SMP mod_unload modversions ")
+    );
+    assert_eq!(modinfo.get_depends(), ["i2c-algo-bit"]);
+    assert!(modinfo.get_parameters().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn modinfo_duplicates() {
+    let mut elf = Elfio::new();
+    let section = elf.add_section(".modinfo");
+    section.set_data(
+        b"parmtype=debug:int\0alias=pci:v1\0parm=debug:Debug level\0alias=pci:v2\0\
+          depends=a,b\0depends=,c\0parm=nodesc\0",
+    );
+
+    let modinfo = ModInfoSectionAccessor::new(&elf, elf.get_section_by_index(1).unwrap());
+    assert_eq!(modinfo.get_entries_num(), 7);
+    assert_eq!(modinfo.get("alias").unwrap(), "pci:v1");
+    assert_eq!(modinfo.get_all("alias"), ["pci:v1", "pci:v2"]);
+    assert_eq!(modinfo.get_license(), None);
+    assert_eq!(modinfo.get_depends(), ["a", "b", "c"]);
+
+    let keys: Vec<&str> = modinfo.get_iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(keys, ["parmtype", "alias", "parm", "alias", "depends", "depends", "parm"]);
+
+    let parameters = modinfo.get_parameters();
+    assert_eq!(parameters.len(), 2);
+    assert_eq!(parameters[0].name, "debug");
+    assert_eq!(parameters[0].description.as_deref(), Some("Debug level"));
+    assert_eq!(parameters[0].ptype.as_deref(), Some("int"));
+    assert_eq!(parameters[1].name, "nodesc");
+    assert_eq!(parameters[1].description.as_deref(), Some(""));
+    assert_eq!(parameters[1].ptype, None);
+}
+
+#[test]
+fn add_strings() -> io::Result<()> {
+    let image = std::fs::read("tests/files/hello_64")?;
+    let mut elf = Elfio::from_bytes(&image)?;
+
+    let section = elf.get_section_by_name(".strtab").unwrap();
+    let offset = section.get_offset() as usize;
+    let size = section.get_size() as ElfWord;
+    let main = section
+        .get_data()
+        .windows(6)
+        .position(|window| window == b"\0main\0")
+        .unwrap() as ElfWord
+        + 1;
+
+    let section = elf.get_section_by_name_mut(".strtab").unwrap();
+    let mut strtab = StringSectionAccessorMut::new(section);
+    assert_eq!(strtab.find_or_add_string("main"), main);
+    assert_eq!(strtab.find_or_add_string("ain"), main + 1);
+    assert_eq!(strtab.find_or_add_string(""), 0);
+    assert_eq!(strtab.add_string("main"), size);
+    assert_eq!(strtab.find_or_add_string("elfio"), size + 5);
+    assert_eq!(strtab.get_string(size + 5), "elfio");
+
+    let section = elf.get_section_by_name(".strtab").unwrap();
+    assert_eq!(section.get_size(), size as ElfXword + 11);
+    assert_eq!(
+        &section.get_data()[..size as usize],
+        &image[offset..offset + size as usize]
+    );
+    let strtab = StringSectionAccessor::new(&elf, section);
+    assert_eq!(strtab.get_string(size), "main");
+
+    Ok(())
+}
+
+#[test]
+fn modify_symbols() -> io::Result<()> {
+    for file_name in ["tests/files/hello_ppc", "tests/files/hello_64"].iter() {
+        let mut elf = Elfio::new();
+        elf.load(&mut BufReader::new(File::open(file_name)?))?;
+
+        let symtab_index = elf.get_section_by_name(".symtab").unwrap().get_index();
+        let info = elf.get_section_by_index(symtab_index).unwrap().get_info() as ElfXword;
+        let mut symtab = SymbolSectionAccessorMut::new(&mut elf, symtab_index).unwrap();
+        let num = symtab.get_symbols_num();
+
+        let global = Symbol {
+            name: "elfio_global".to_string(),
+            value: 0x1000,
+            size: 0x10,
+            bind: constant::STB_GLOBAL,
+            stype: constant::STT_FUNC,
+            other: 0,
+            shndx: 12,
+            special_shndx: false,
+            version: None,
+        };
+        assert_eq!(symtab.add_symbol(&global, |_, _| panic!()), Some(num));
+        // The local symbol is inserted in front of the global ones
+        let local = Symbol {
+            name: "elfio_local".to_string(),
+            bind: constant::STB_LOCAL,
+            stype: constant::STT_OBJECT,
+            ..global.clone()
+        };
+        let mut moved = Vec::new();
+        assert_eq!(symtab.add_symbol(&local, |old, new| moved.push((old, new))), Some(info));
+        assert_eq!(moved, (info..=num).rev().map(|i| (i, i + 1)).collect::<Vec<_>>());
+        assert_eq!(symtab.get_symbol(info).unwrap().name, "elfio_local");
+        assert_eq!(symtab.get_symbol(num + 1).unwrap().name, "elfio_global");
+        symtab.arrange_local_symbols(|_, _| panic!());
+
+        let renamed = Symbol {
+            name: "main".to_string(),
+            ..local.clone()
+        };
+        assert_eq!(symtab.update_symbol(info, &renamed, |_, _| panic!()), Some(info));
+        assert_eq!(symtab.update_symbol(num + 2, &renamed, |_, _| ()), None);
+        // The global symbol becoming local is moved in front of the global ones
+        let mut moved = Vec::new();
+        let index = symtab.update_symbol(num + 1, &local, |old, new| moved.push((old, new)));
+        assert_eq!(index, Some(info + 1));
+        let mut expected: Vec<_> = (info + 1..=num).rev().map(|i| (i, i + 1)).collect();
+        expected.push((num + 1, info + 1));
+        assert_eq!(moved, expected);
+        // The global symbols are moved back on the removal
+        let mut moved = Vec::new();
+        assert!(symtab.remove_symbol(info + 1, |old, new| moved.push((old, new))));
+        assert_eq!(moved, (info + 2..num + 2).map(|i| (i, i - 1)).collect::<Vec<_>>());
+        assert!(!symtab.remove_symbol(num + 1, |_, _| ()));
+
+        let mut writer = io::Cursor::new(Vec::new());
+        elf.save(&mut writer)?;
+        let mut elf = Elfio::new();
+        elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+        let section = elf.get_section_by_name(".symtab").unwrap();
+        assert_eq!(section.get_info() as ElfXword, info + 1);
+        let symtab = SymbolSectionAccessor::new(&elf, section);
+        assert_eq!(symtab.get_symbols_num(), num + 1);
+        let sym = symtab.get_symbol(info).unwrap();
+        assert_eq!(sym.name, "main");
+        assert_eq!(sym.value, 0x1000);
+        assert_eq!(sym.size, 0x10);
+        assert_eq!(sym.bind, constant::STB_LOCAL);
+        assert_eq!(sym.stype, constant::STT_OBJECT);
+        assert_eq!(sym.shndx, 12);
+        assert_ne!(symtab.get_symbol(num).unwrap().bind, constant::STB_LOCAL);
+
+        let entry_size = section.get_entry_size();
+        for size in [entry_size - 1, 0x7FFF_FFFF_FFFF].iter() {
+            elf.get_section_by_index_mut(symtab_index).unwrap().set_entry_size(*size);
+            assert!(SymbolSectionAccessorMut::new(&mut elf, symtab_index).is_none());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn modify_symbols_names_and_info() {
+    let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2LSB);
+    let strtab = elf.add_section(".strtab");
+    strtab.set_type(constant::SHT_STRTAB);
+    let strtab_index = strtab.get_index();
+    let symtab = elf.add_section(".symtab");
+    symtab.set_type(constant::SHT_SYMTAB);
+    let symtab_index = symtab.get_index();
+
+    // Names can't be added without a linked string table
+    let mut symtab = SymbolSectionAccessorMut::new(&mut elf, symtab_index).unwrap();
+    let local = Symbol {
+        name: "main".to_string(),
+        bind: constant::STB_LOCAL,
+        ..Default::default()
+    };
+    assert_eq!(symtab.add_symbol(&local, |_, _| ()), None);
+    assert_eq!(symtab.get_symbols_num(), 0);
+    elf.get_section_by_index_mut(symtab_index).unwrap().set_link(strtab_index);
+
+    let mut symtab = SymbolSectionAccessorMut::new(&mut elf, symtab_index).unwrap();
+    assert_eq!(symtab.add_symbol(&local, |_, _| ()), Some(1));
+    let suffix = Symbol {
+        name: "ain".to_string(),
+        ..local.clone()
+    };
+    assert_eq!(symtab.add_symbol(&suffix, |_, _| ()), Some(2));
+    let global = Symbol {
+        name: "main".to_string(),
+        bind: constant::STB_GLOBAL,
+        ..Default::default()
+    };
+    assert_eq!(symtab.add_symbol(&global, |_, _| ()), Some(3));
+    assert_eq!(symtab.update_symbol(2, &global, |_, _| ()), Some(2));
+    assert!(symtab.remove_symbol(1, |_, _| ()));
+
+    // The names are shared in the string table
+    let section = elf.get_section_by_index(strtab_index).unwrap();
+    assert_eq!(section.get_data(), b"\0main\0");
+    let section = elf.get_section_by_index(symtab_index).unwrap();
+    assert_eq!(section.get_info(), 1);
+    let symtab = SymbolSectionAccessor::new(&elf, section);
+    assert_eq!(symtab.get_symbols_num(), 3);
+    assert_eq!(symtab.get_symbol(1).unwrap().name, "main");
+    assert_eq!(symtab.get_symbol(2).unwrap().name, "main");
+}
+
+#[test]
+fn write_relocations() -> io::Result<()> {
+    let configs = [
+        (constant::ELFCLASS32, constant::ELFDATA2LSB, constant::SHT_REL, 8),
+        (constant::ELFCLASS32, constant::ELFDATA2MSB, constant::SHT_RELA, 12),
+        (constant::ELFCLASS64, constant::ELFDATA2MSB, constant::SHT_REL, 16),
+        (constant::ELFCLASS64, constant::ELFDATA2LSB, constant::SHT_RELA, 24),
+    ];
+
+    for (class, encoding, section_type, entry_size) in configs.iter() {
+        let mut elf = Elfio::new_(*class, *encoding);
+        let section = elf.add_section(".rel");
+        section.set_type(*section_type);
+        let index = section.get_index();
+
+        let mut relocs = RelocationSectionAccessorMut::new(&mut elf, index).unwrap();
+        for i in 0..3 {
+            let rel = Relocation {
+                offset: 0x100 + i * 4,
+                symbol: 0x12 + i as ElfWord,
+                rtype:  2,
+                addend: Some(-(i as ElfSxword)),
+            };
+            assert_eq!(relocs.add_entry(&rel), i);
+        }
+        let rel = Relocation {
+            offset: 0x200,
+            symbol: 0x34,
+            rtype:  0x56,
+            addend: None,
+        };
+        assert!(relocs.set_entry(1, &rel));
+        assert!(!relocs.set_entry(3, &rel));
+
+        let mut writer = io::Cursor::new(Vec::new());
+        elf.save(&mut writer)?;
+        let mut elf = Elfio::new();
+        elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+        let section = elf.get_section_by_name(".rel").unwrap();
+        assert_eq!(section.get_entry_size(), *entry_size);
+        assert_eq!(section.get_size(), 3 * entry_size);
+
+        let is_rela = *section_type == constant::SHT_RELA;
+        let relocs = RelocationSectionAccessor::new(&elf, section);
+        assert_eq!(relocs.get_entries_num(), 3);
+        let rel = relocs.get_entry(2).unwrap();
+        assert_eq!(rel.offset, 0x108);
+        assert_eq!(rel.symbol, 0x14);
+        assert_eq!(rel.rtype, 2);
+        assert_eq!(rel.addend, if is_rela { Some(-2) } else { None });
+        let rel = relocs.get_entry(1).unwrap();
+        assert_eq!(rel.offset, 0x200);
+        assert_eq!(rel.symbol, 0x34);
+        assert_eq!(rel.rtype, 0x56);
+        assert_eq!(rel.addend, if is_rela { Some(0) } else { None });
+
+        let index = section.get_index();
+        for size in [*entry_size - 1, 0x7FFF_FFFF_FFFF].iter() {
+            elf.get_section_by_index_mut(index).unwrap().set_entry_size(*size);
+            assert!(RelocationSectionAccessorMut::new(&mut elf, index).is_none());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn modify_dynamic() -> io::Result<()> {
+    for file_name in ["tests/files/hello_64", "tests/files/hello_ppc"].iter() {
+        let mut elf = Elfio::new();
+        elf.load(&mut BufReader::new(File::open(file_name)?))?;
+
+        let section = elf.get_section_by_name(".dynamic").unwrap();
+        let index = section.get_index();
+        let size = section.get_size();
+        let capacity = size / section.get_entry_size();
+        let dynstr_index = section.get_link();
+        let dynstr_size = elf.get_section_by_index(dynstr_index).unwrap().get_size();
+        let segments_num = elf.get_segments().len();
+
+        let mut dyns = DynamicSectionAccessorMut::new(&mut elf, index).unwrap();
+        let num = dyns.get_entries_num();
+        let first = dyns.get_entry(0).unwrap();
+        assert_eq!(first.tag, constant::DT_NEEDED);
+
+        // The strings present in the loaded string table are used in place
+        assert_eq!(dyns.add_needed("libc.so.6").unwrap(), num - 1);
+        dyns.set_flags_1(1).unwrap();
+        dyns.set_flags_1(8).unwrap();
+        assert!(dyns.remove_entry(0));
+        assert!(!dyns.remove_entry(num + 1));
+        assert_eq!(dyns.get_entries_num(), num + 1);
+        assert!(dyns.set_entry(0, &first));
+
+        // The loaded table gets the spare entries first
+        let needed = Dynamic {
+            tag:   constant::DT_NEEDED,
+            value: first.value,
+        };
+        for i in num..capacity - 1 {
+            assert_eq!(dyns.add_entry(&needed).unwrap(), i);
+        }
+        assert!(dyns.remove_entry(num));
+        assert!(dyns.remove_entry(num));
+        assert_eq!(dyns.get_entries_num(), capacity - 2);
+
+        // New strings and entries move the tables to a new segment
+        assert_eq!(dyns.add_needed("libelfio.so.1").unwrap(), capacity - 3);
+        dyns.set_runpath("$ORIGIN/lib").unwrap();
+        dyns.set_flags(1).unwrap();
+        assert_eq!(dyns.get_entries_num(), capacity + 1);
+
+        let mut writer = io::Cursor::new(Vec::new());
+        elf.save(&mut writer)?;
+        let mut elf = Elfio::new();
+        elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+        let section = elf.get_section_by_name(".dynamic").unwrap();
+        let dynstr = elf.get_section_by_index(dynstr_index).unwrap();
+        let strings = StringSectionAccessor::new(&elf, dynstr);
+        let dyns = DynamicSectionAccessor::new(&elf, section);
+        assert!(section.get_size() > size);
+        assert_eq!(dynstr.get_size(), dynstr_size + 26);
+        assert_eq!(dyns.get_entries_num(), capacity + 1);
+
+        let entries: Vec<Dynamic> = (0..capacity).map(|i| dyns.get_entry(i).unwrap()).collect();
+        let find = |tag| entries.iter().find(|entry| entry.tag == tag).unwrap();
+        assert_eq!(entries[0].tag, constant::DT_NEEDED);
+        assert_eq!(entries[0].value, first.value);
+        assert_eq!(strings.get_string(entries[num as usize - 2].value as ElfWord), "libc.so.6");
+        let needed = entries[capacity as usize - 3].value as ElfWord;
+        assert_eq!(strings.get_string(needed), "libelfio.so.1");
+        let runpath = find(constant::DT_RUNPATH).value as ElfWord;
+        assert_eq!(strings.get_string(runpath), "$ORIGIN/lib");
+        assert_eq!(find(constant::DT_FLAGS_1).value, 8);
+        assert_eq!(find(constant::DT_STRSZ).value, dynstr.get_size());
+        assert_eq!(find(constant::DT_STRTAB).value, dynstr.get_address());
+
+        // The new loadable segment follows the other ones in memory and
+        // holds the tables and the program header table
+        let segments = elf.get_segments();
+        assert_eq!(segments.len(), segments_num + 1);
+        let segment = &segments[segments_num];
+        assert_eq!(segment.get_type(), constant::PT_LOAD);
+        assert_eq!(segment.get_virtual_address(), dynstr.get_address());
+        assert_eq!(segment.get_virtual_address() % segment.get_align(), 0);
+        assert!(segments[..segments_num]
+            .iter()
+            .filter(|other| other.get_type() == constant::PT_LOAD)
+            .all(|other| {
+                other.get_virtual_address() + other.get_memory_size()
+                    <= segment.get_virtual_address()
+            }));
+        assert_eq!(segment.get_section_indices(), [dynstr_index, index]);
+        let phdr = segments.iter().find(|s| s.get_type() == constant::PT_PHDR).unwrap();
+        assert_eq!(phdr.get_offset(), elf.get_segments_offset());
+        assert_eq!(
+            phdr.get_offset() - segment.get_offset(),
+            phdr.get_virtual_address() - segment.get_virtual_address()
+        );
+        assert!(phdr.get_offset() + phdr.get_file_size()
+            <= segment.get_offset() + segment.get_file_size());
+        let dynamic = segments.iter().find(|s| s.get_type() == constant::PT_DYNAMIC).unwrap();
+        assert_eq!(dynamic.get_offset(), section.get_offset());
+        assert_eq!(dynamic.get_virtual_address(), section.get_address());
+        assert_eq!(dynamic.get_file_size(), section.get_size());
+
+        let entry_size = section.get_entry_size();
+        for size in [entry_size - 1, 0x7FFF_FFFF_FFFF].iter() {
+            elf.get_section_by_index_mut(index).unwrap().set_entry_size(*size);
+            assert!(DynamicSectionAccessorMut::new(&mut elf, index).is_none());
+        }
+    }
+
+    // Strings can't be added without a linked string table
+    let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+    let dynamic = elf.add_section(".dynamic");
+    dynamic.set_type(constant::SHT_DYNAMIC);
+    let index = dynamic.get_index();
+    let mut dyns = DynamicSectionAccessorMut::new(&mut elf, index).unwrap();
+    assert!(matches!(
+        dyns.add_needed("libc.so.6"),
+        Err(ElfioError::MissingStringTable { section }) if section == index
+    ));
+    dyns.set_flags(1).unwrap();
+
+    Ok(())
+}
+
+#[test]
+fn add_notes() -> io::Result<()> {
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_ppc")?))?;
+
+    // The loaded note section belongs to segments, so its notes are
+    // copied to a new section to be extended
+    let data = elf.get_section_by_name(".note.ABI-tag").unwrap().get_data().to_vec();
+    let section = elf.add_section(".note.elfio");
+    section.set_type(constant::SHT_NOTE);
+    section.set_addr_align(4);
+    section.set_data(&data);
+    let index = section.get_index();
+    let mut notes = NoteSectionAccessorMut::new(&mut elf, index).unwrap();
+    assert_eq!(notes.add_note(0x100, "elfio", &[1, 2, 3]), 1);
+    assert_eq!(notes.add_note(0x101, "", &[]), 2);
+
+    let section = elf.add_section(".note.gnu.property");
+    section.set_type(constant::SHT_NOTE);
+    section.set_addr_align(8);
+    let property_index = section.get_index();
+    let mut notes = NoteSectionAccessorMut::new(&mut elf, property_index).unwrap();
+    let property = [0x02, 0x00, 0x00, 0xc0, 0x04, 0x00, 0x00, 0x00, 0x03, 0, 0, 0, 0, 0, 0, 0];
+    assert_eq!(notes.add_note(5, "GNU", &property), 0);
+    assert_eq!(notes.add_note(6, "elfio", &[0xFF]), 1);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+    let section = elf.get_section_by_index(index).unwrap();
+    let notes = NoteSectionAccessor::new(&elf, section);
+    assert_eq!(notes.get_notes_num(), 3);
+    assert_eq!(notes.get_note(0).unwrap().name, "GNU");
+    let note = notes.get_note(1).unwrap();
+    assert_eq!(note.ntype, 0x100);
+    assert_eq!(note.name, "elfio");
+    assert_eq!(note.description, [1, 2, 3]);
+    let note = notes.get_note(2).unwrap();
+    assert_eq!(note.ntype, 0x101);
+    assert_eq!(note.name, "");
+    assert!(note.description.is_empty());
+
+    let section = elf.get_section_by_index(property_index).unwrap();
+    assert_eq!(section.get_size(), 32 + 32);
+    assert_eq!(&section.get_data()[..16], b"\0\0\0\x04\0\0\0\x10\0\0\0\x05GNU\0");
+    let notes = NoteSectionAccessor::new(&elf, section);
+    assert_eq!(notes.get_notes_num(), 2);
+    assert_eq!(notes.get_note(0).unwrap().description, property);
+    let note = notes.get_note(1).unwrap();
+    assert_eq!(note.ntype, 6);
+    assert_eq!(note.name, "elfio");
+    assert_eq!(note.description, [0xFF]);
+
+    Ok(())
+}
+
+#[test]
+fn write_arrays() -> io::Result<()> {
+    let configs = [
+        (constant::ELFCLASS64, constant::ELFDATA2LSB, constant::SHT_RELA),
+        (constant::ELFCLASS32, constant::ELFDATA2MSB, constant::SHT_REL),
+    ];
+
+    for (class, encoding, rel_type) in configs.iter() {
+        let mut elf = Elfio::new_(*class, *encoding);
+        elf.set_type(constant::ET_REL);
+        let section = elf.add_section(".init_array");
+        section.set_type(constant::SHT_INIT_ARRAY);
+        let index = section.get_index();
+
+        let mut array = ArraySectionAccessorMut::new(&mut elf, index).unwrap();
+        assert_eq!(array.add_entry_with_relocation(0x20, 1, 1), None);
+        assert_eq!(array.add_entry(0x1234), 0);
+
+        let section = elf.add_section(".rel.init_array");
+        section.set_type(*rel_type);
+        section.set_info(index as ElfWord);
+        let rel_index = section.get_index();
+
+        let mut array = ArraySectionAccessorMut::new(&mut elf, index).unwrap();
+        assert_eq!(array.add_entry_with_relocation(0x20, 2, 1), Some(1));
+        assert!(array.set_entry(0, 0x5678));
+        assert!(!array.set_entry(2, 0x5678));
+
+        let mut writer = io::Cursor::new(Vec::new());
+        elf.save(&mut writer)?;
+        let mut elf = Elfio::new();
+        elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+        let is_rela = *rel_type == constant::SHT_RELA;
+        let section = elf.get_section_by_index(index).unwrap();
+        let array = ArraySectionAccessor::new(&elf, section);
+        assert_eq!(array.get_entries_num(), 2);
+        assert_eq!(array.get_entry(0).unwrap().value, 0x5678);
+        assert_eq!(array.get_entry(1).unwrap().value, if is_rela { 0 } else { 0x20 });
+
+        let section = elf.get_section_by_index(rel_index).unwrap();
+        let relocs = RelocationSectionAccessor::new(&elf, section);
+        assert_eq!(relocs.get_entries_num(), 1);
+        let rel = relocs.get_entry(0).unwrap();
+        assert_eq!(rel.offset, if *class == constant::ELFCLASS64 { 8 } else { 4 });
+        assert_eq!(rel.symbol, 2);
+        assert_eq!(rel.rtype, 1);
+        assert_eq!(rel.addend, if is_rela { Some(0x20) } else { None });
+    }
+
+    Ok(())
+}
+
+#[test]
+fn modify_modinfo() -> io::Result<()> {
+    let elf_file = File::open("tests/files/i2c-gpio.ko")?;
+    let mut reader = BufReader::new(elf_file);
+
+    let mut elf = Elfio::new();
+    elf.load(&mut reader)?;
+
+    let index = elf.get_section_by_name(".modinfo").unwrap().get_index();
+    let mut modinfo = ModInfoSectionAccessorMut::new(&mut elf, index).unwrap();
+    assert_eq!(modinfo.get_entries_num(), 10);
+    let vermagic = modinfo.get("vermagic").unwrap().to_string();
+    let first_key = modinfo.get_iter().next().unwrap().0.clone();
+
+    modinfo.add("alias", "of:N*T*Cfoo,bar");
+    modinfo.add("alias", "of:N*T*Cfoo,baz");
+    modinfo.set("vermagic", "5.10.0 SMP mod_unload");
+    assert_eq!(modinfo.remove("description"), 1);
+    assert_eq!(modinfo.remove("description"), 0);
+    assert!(ModInfoSectionAccessorMut::new(&mut elf, 100).is_none());
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let mut elf = Elfio::new();
+    elf.load(&mut io::Cursor::new(writer.into_inner()))?;
+
+    let index = elf.get_section_by_name(".modinfo").unwrap().get_index();
+    let modinfo = ModInfoSectionAccessorMut::new(&mut elf, index).unwrap();
+    let aliases = modinfo.get_all("alias");
+    assert_eq!(aliases[aliases.len() - 2..], ["of:N*T*Cfoo,bar", "of:N*T*Cfoo,baz"]);
+    assert_eq!(modinfo.get_all("vermagic"), ["5.10.0 SMP mod_unload"]);
+    assert_ne!(vermagic, "5.10.0 SMP mod_unload");
+    assert_eq!(modinfo.get("description"), None);
+    assert_eq!(modinfo.get_iter().next().unwrap().0, first_key);
+    assert_eq!(modinfo.get_entries_num(), 11);
+
+    Ok(())
+}
+
+#[test]
+fn address_space() -> io::Result<()> {
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_ppc")?))?;
+
+    let memory = AddressSpace::new(&elf);
+    assert_eq!(memory.read_u32(0x10000000), Some(0x7F454C46));
+    assert_eq!(memory.read_u32(0x10000550), Some(0x7c290b78));
+    assert_eq!(memory.read_u16(0x10000550), Some(0x7c29));
+    assert_eq!(memory.read(0x1000013c, 16).unwrap()[..5], *b"so.1\0");
+    assert_eq!(memory.read(0x10010c08, 8).unwrap()[4..], [0, 0, 0, 0]);
+    assert_eq!(memory.read_u64(0x10010c9c), Some(0));
+    assert_eq!(memory.read(0x10010ca0, 4).unwrap(), [0, 0, 0, 0]);
+    assert_eq!(memory.read(0x10010ca0, 5), None);
+    assert_eq!(memory.read(0x10000ac8, 8), None);
+    assert_eq!(memory.read(0x0fffffff, 1), None);
+
+    assert_eq!(memory.get_file_offset(0x10010acc), Some(0xacc));
+    assert_eq!(memory.get_file_offset(0x10010c0c), None);
+    assert_eq!(memory.get_file_offset(0x10000acc), None);
+    assert_eq!(memory.get_address(0xacb), Some(0x10000acb));
+    assert_eq!(memory.get_address(0xc0b), Some(0x10010c0b));
+    assert_eq!(memory.get_address(0xc0c), None);
+
+    let mut memory = AddressSpaceMut::new(&mut elf);
+    // The range covers .interp, the padding and .note.ABI-tag
+    assert!(memory.write(0x1000013f, &[1, 2, 3, 4, 5, 6]));
+    assert!(memory.write_u32(0x10010c08, 0xdeadbeef));
+    assert!(!memory.write_u32(0x10010c0a, 0));
+    assert!(!memory.write(0x10000ac8, &[0; 8]));
+    assert_eq!(memory.read(0x10010c08, 6).unwrap(), [0xde, 0xad, 0xbe, 0xef, 0, 0]);
+
+    let section = elf.get_section_by_name(".interp").unwrap();
+    assert_eq!(section.get_data()[11..], [1, 2]);
+    let section = elf.get_section_by_name(".note.ABI-tag").unwrap();
+    assert_eq!(section.get_data()[..2], [6, 0]);
+
+    let mut writer = io::Cursor::new(Vec::new());
+    elf.save(&mut writer)?;
+    let data = writer.into_inner();
+    assert_eq!(data[0x13f..0x145], [1, 2, 3, 4, 5, 6]);
+    let elf = Elfio::from_bytes(&data).unwrap();
+    let memory = AddressSpace::new(&elf);
+    assert_eq!(memory.read_u32(0x10010c08), Some(0xdeadbeef));
+    assert_eq!(memory.read(0x1000013f, 6).unwrap(), [1, 2, 3, 4, 5, 6]);
+
+    Ok(())
+}
+
+#[test]
+fn address_space_rel() -> io::Result<()> {
+    let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+    elf.set_type(constant::ET_REL);
+    let section = elf.add_section(".text");
+    section.set_type(constant::SHT_PROGBITS);
+    section.set_flags(constant::SHF_ALLOC | constant::SHF_EXECINSTR);
+    section.set_address(0x1000);
+    section.set_data(&[0x90; 16]);
+    let section = elf.add_section(".bss");
+    section.set_type(constant::SHT_NOBITS);
+    section.set_flags(constant::SHF_ALLOC | constant::SHF_WRITE);
+    section.set_address(0x1010);
+    section.set_size(8);
+    let section = elf.add_section(".comment");
+    section.set_type(constant::SHT_PROGBITS);
+    section.set_data(b"elfio");
+
+    let mut memory = AddressSpaceMut::new(&mut elf);
+    assert!(memory.write_u64(0x1008, 0x0102030405060708));
+    assert!(!memory.write_u16(0x100f, 0));
+    assert_eq!(memory.read(0x100c, 8).unwrap(), [4, 3, 2, 1, 0, 0, 0, 0]);
+    assert_eq!(memory.read(0x1014, 8), None);
+    assert_eq!(memory.read(0, 1), None);
+
+    let section = elf.get_section_by_name(".text").unwrap();
+    assert_eq!(section.get_data()[8..], [8, 7, 6, 5, 4, 3, 2, 1]);
+
+    // The section data is modified in place
+    let text = section.get_data().as_ptr();
+    let mut memory = AddressSpaceMut::new(&mut elf);
+    assert!(memory.write_u16(0x1000, 0));
+    let section = elf.get_section_by_name(".text").unwrap();
+    assert_eq!(section.get_data().as_ptr(), text);
+    assert_eq!(section.get_data()[..3], [0, 0, 0x90]);
+
+    let memory = AddressSpace::new(&elf);
+    assert_eq!(memory.read_u64(0x1008), Some(0x0102030405060708));
+    assert_eq!(memory.read_u32(0x1014), Some(0));
+    assert_eq!(memory.get_file_offset(0x1010), None);
+
+    // The section size is set, but there is no data
+    let section = elf.add_section(".rodata");
+    section.set_type(constant::SHT_PROGBITS);
+    section.set_flags(constant::SHF_ALLOC);
+    section.set_address(0x2000);
+    section.set_size(16);
+    let mut memory = AddressSpaceMut::new(&mut elf);
+    assert!(!memory.write_u32(0x2000, 1));
+    assert!(!memory.write(0x100c, &[0; 0x1000]));
+    assert_eq!(memory.read(0x2000, 4), None);
+    assert_eq!(memory.read(0x100c, 4).unwrap(), [4, 3, 2, 1]);
+
+    // A segment laid out by its sizes only
+    let mut elf = Elfio::new_(constant::ELFCLASS32, constant::ELFDATA2MSB);
+    elf.set_type(constant::ET_EXEC);
+    let segment = elf.add_segment();
+    segment.set_type(constant::PT_LOAD);
+    segment.set_virtual_address(0x10000);
+    segment.set_file_size(0x10);
+    segment.set_memory_size(0x20);
+    let mut memory = AddressSpaceMut::new(&mut elf);
+    assert!(!memory.write_u16(0x10000, 1));
+    assert!(!memory.write_u16(0x10010, 1));
+    assert_eq!(memory.read(0x10000, 2), None);
+    assert_eq!(memory.read(0x10010, 2).unwrap(), [0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn load_ppc_image() -> io::Result<()> {
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_ppc")?))?;
+
+    let base = 0x10000000;
+    let mut memory = vec![0xFF; 0x20000];
+    let mut permissions = Vec::new();
+    let writer = zero_filling_writer(|address, data, perm| {
+        let start = (address - base) as usize;
+        memory[start..start + data.len()].copy_from_slice(data);
+        if permissions.last() != Some(&perm) {
+            permissions.push(perm);
+        }
+        Ok(())
+    });
+    let image = load_image(&elf, writer)?;
+
+    let read_u32 = |address: Elf64Addr| {
+        let start = (address - base) as usize;
+        u32::from_be_bytes([memory[start], memory[start + 1], memory[start + 2], memory[start + 3]])
+    };
+    assert_eq!(read_u32(base), 0x7F454C46);
+    assert_eq!(read_u32(image.entry), 0x7c290b78);
+    assert_eq!(read_u32(0x10010c08), 0);
+    assert_eq!(read_u32(0x10010ca0), 0);
+    assert_eq!(read_u32(0x10010ca4), 0xFFFFFFFF);
+
+    let rx = Permissions {
+        read:    true,
+        write:   false,
+        execute: true,
+    };
+    let rw = Permissions::from_flags(constant::PF_R | constant::PF_W);
+    assert_eq!(permissions, [rx, rw]);
+    assert_eq!(image.entry, 0x10000550);
+    assert_eq!(image.program_break, 0x10010ca4);
+    let auxv = [
+        (constant::AT_PHDR, 0x10000034),
+        (constant::AT_PHENT, 32),
+        (constant::AT_PHNUM, 8),
+        (constant::AT_ENTRY, 0x10000550),
+    ];
+    assert_eq!(image.auxv, auxv);
+
+    // Without PT_PHDR the program headers are found in the first PT_LOAD
+    elf.get_segment_by_index_mut(0).unwrap().set_type(constant::PT_NULL);
+    let image = load_image(&elf, |_, _, _, _| Ok(()))?;
+    assert_eq!(image.auxv[0], (constant::AT_PHDR, 0x10000034));
+
+    let result = load_image(&elf, |_, _, _, _| Err(io::Error::other("full")));
+    assert!(matches!(result, Err(ElfioError::Io(_))));
+
+    elf.get_segment_by_index_mut(3).unwrap().set_memory_size(0xF0000000);
+    let result = load_image(&elf, |_, _, _, _| Ok(()));
+    assert!(matches!(
+        result,
+        Err(ElfioError::SegmentAddressOutOfRange {
+            index:   3,
+            address: 0x10010acc,
+            size:    0xF0000000,
+        })
+    ));
+
+    // The zero bytes are not allocated by the loader
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/hello_64")?))?;
+    elf.get_segment_by_index_mut(3).unwrap().set_memory_size(1 << 62);
+    let mut ranges = Vec::new();
+    let image = load_image(&elf, |address, data, zeros, _| {
+        ranges.push((address, data.len(), zeros));
+        Ok(())
+    })?;
+    assert_eq!(ranges, [(0x400000, 0x66c, 0), (0x600670, 0x1ec, (1 << 62) - 0x1ec)]);
+    assert_eq!(image.program_break, 0x600670 + (1 << 62));
+
+    let mut elf = Elfio::new();
+    elf.load(&mut BufReader::new(File::open("tests/files/i2c-gpio.ko")?))?;
+    let result = load_image(&elf, |_, _, _, _| Ok(()));
+    assert!(matches!(result, Err(ElfioError::UnsupportedFileType(constant::ET_REL))));
+
+    Ok(())
+}
+
+fn walk_accessors(elf: &Elfio) {
+    for section in elf.get_sections() {
+        let section = &**section;
+        let _ = section.get_name();
+        match section.get_type() {
+            constant::SHT_STRTAB => {
+                let strings = StringSectionAccessor::new(elf, section);
+                for pos in (0..section.get_data().len() as ElfWord + 2).step_by(7) {
+                    let _ = strings.get_string(pos);
+                }
+            }
+            constant::SHT_SYMTAB | constant::SHT_DYNSYM => {
+                let symbols = SymbolSectionAccessor::new(elf, section);
+                for i in 0..=symbols.get_symbols_num() {
+                    let _ = symbols.get_symbol(i);
+                }
+                let _ = symbols.find_by_name("main");
+                let _ = symbols.find_by_address(0x1000);
+            }
+            constant::SHT_REL | constant::SHT_RELA => {
+                let relocations = RelocationSectionAccessor::new(elf, section);
+                for i in 0..=relocations.get_entries_num() {
+                    let _ = relocations.get_entry(i);
+                }
+            }
+            constant::SHT_DYNAMIC => {
+                let dynamic = DynamicSectionAccessor::new(elf, section);
+                for i in 0..=dynamic.get_entries_num() {
+                    let _ = dynamic.get_entry(i);
+                }
+            }
+            constant::SHT_NOTE => {
+                let notes = NoteSectionAccessor::new(elf, section);
+                for i in 0..=notes.get_notes_num() {
+                    let _ = notes.get_note(i);
+                }
+            }
+            constant::SHT_INIT_ARRAY | constant::SHT_FINI_ARRAY | constant::SHT_PREINIT_ARRAY => {
+                let array = ArraySectionAccessor::new(elf, section);
+                for i in 0..=array.get_entries_num() {
+                    let _ = array.get_entry(i);
+                }
+            }
+            constant::SHT_HASH => {
+                let hash = HashSectionAccessor::new(elf, section);
+                let _ = hash.get_chain(hash.get_chains_num().saturating_sub(1));
+                let _ = hash.find_symbol("main");
+            }
+            constant::SHT_GNU_HASH => {
+                let hash = GnuHashSectionAccessor::new(elf, section);
+                let _ = hash.get_chain(hash.get_chains_num().saturating_sub(1));
+                let _ = hash.find_symbol("main");
+            }
+            constant::SHT_GNU_VERDEF => {
+                let _ = VerdefSectionAccessor::new(elf, section).get_entries();
+            }
+            constant::SHT_GNU_VERNEED => {
+                let _ = VerneedSectionAccessor::new(elf, section).get_entries();
+            }
+            _ => {
+                let _ = ModInfoSectionAccessor::new(elf, section).get_entries_num();
+            }
+        }
+    }
+
+    let memory = AddressSpace::new(elf);
+    for segment in elf.get_segments() {
+        let _ = memory.read(segment.get_virtual_address(), 64);
+        let _ = memory.get_address(segment.get_offset());
+    }
+}
+
+#[test]
+fn malformed_input() -> io::Result<()> {
+    let files = [
+        "tests/files/hello_32",
+        "tests/files/hello_64",
+        "tests/files/hello_ppc",
+        "tests/files/hello_ppc64",
+        "tests/files/i2c-gpio.ko",
+    ];
+
+    for file_name in files.iter() {
+        let original = std::fs::read(file_name)?;
+
+        // Truncated files
+        for len in (0..original.len()).step_by(211) {
+            let mut elf = Elfio::new();
+            if elf.load(&mut io::Cursor::new(&original[..len])).is_ok() {
+                walk_accessors(&elf);
+            }
+        }
+
+        // Corrupted file header, section headers and section data
+        let mut elf = Elfio::new();
+        elf.load(&mut io::Cursor::new(&original[..]))?;
+        let mut positions: Vec<usize> = (16..64).collect();
+        let shoff = elf.get_sections_offset() as usize;
+        positions.extend((shoff..original.len()).step_by(5));
+        for section in elf.get_sections() {
+            let offset = section.get_offset() as usize;
+            positions.extend((offset..offset + 32).filter(|pos| *pos < original.len()));
+        }
+
+        for pos in positions {
+            for value in [0x00u8, 0x7F, 0xFF].iter() {
+                let mut data = original.clone();
+                data[pos] = *value;
+
+                let mut elf = Elfio::new();
+                if elf.load(&mut io::Cursor::new(&data[..])).is_ok() {
+                    walk_accessors(&elf);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn malformed_modinfo() -> io::Result<()> {
+    let mut elf = Elfio::new_(constant::ELFCLASS64, constant::ELFDATA2LSB);
+    let section = elf.add_section(".modinfo");
+    section.set_type(constant::SHT_PROGBITS);
+    section.set_data(b"license=GPL\0no separator\0\xFF\xFE=\x80\0author=");
+
+    let modinfo = ModInfoSectionAccessor::new(&elf, elf.get_section_by_index(1).unwrap());
+    assert_eq!(modinfo.get_entries_num(), 3);
+    assert_eq!(modinfo.get("license").unwrap(), "GPL");
+    assert_eq!(modinfo.get("author").unwrap(), "");
+
+    let strings = StringSectionAccessor::new(&elf, elf.get_section_by_index(1).unwrap());
+    assert_eq!(strings.get_string(0), "license=GPL");
+    assert_eq!(strings.get_string(1000), "");
 
     Ok(())
 }